@@ -0,0 +1,36 @@
+pragma circom 2.1.0;
+
+include "circomlib/circuits/bitify.circom";
+include "circomlib/circuits/comparators.circom";
+include "circomlib/circuits/poseidon.circom";
+
+// Proves reputation > threshold without revealing the reputation.
+// The on-chain AgentProfile.reputation_commitment binds the witness to the
+// prover's current score: commitment = Poseidon(reputation, blinding), set by
+// commit_reputation. The blinding factor is the owner's secret and never goes
+// on-chain.
+// Public inputs (in order): threshold, commitment
+template ReputationAbove(n) {
+    signal input threshold;
+    signal input commitment;
+    signal input reputation; // witness
+    signal input blinding;   // witness, kept off-chain
+
+    // Both values are u64 on-chain; range-check before comparing
+    component repBits = Num2Bits(n);
+    repBits.in <== reputation;
+    component thresholdBits = Num2Bits(n);
+    thresholdBits.in <== threshold;
+
+    component hasher = Poseidon(2);
+    hasher.inputs[0] <== reputation;
+    hasher.inputs[1] <== blinding;
+    hasher.out === commitment;
+
+    component gt = GreaterThan(n);
+    gt.in[0] <== reputation;
+    gt.in[1] <== threshold;
+    gt.out === 1;
+}
+
+component main {public [threshold, commitment]} = ReputationAbove(64);
@@ -0,0 +1,20 @@
+pragma circom 2.1.0;
+
+include "circomlib/circuits/poseidon.circom";
+
+// Proves commitment = Poseidon(reputation, blinding) for the prover's public
+// on-chain reputation, without revealing the blinding factor. commit_reputation
+// checks it before storing the commitment that statement proofs are bound to.
+// Public inputs (in order): reputation, commitment
+template ReputationCommitment() {
+    signal input reputation;
+    signal input commitment;
+    signal input blinding; // witness, kept off-chain
+
+    component hasher = Poseidon(2);
+    hasher.inputs[0] <== reputation;
+    hasher.inputs[1] <== blinding;
+    hasher.out === commitment;
+}
+
+component main {public [reputation, commitment]} = ReputationCommitment();
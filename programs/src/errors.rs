@@ -61,4 +61,130 @@ pub enum ReputationError {
     
     #[msg("Invalid proof")]
     InvalidProof,
+    
+    #[msg("Task issuer not active")]
+    IssuerInactive,
+    
+    #[msg("Task issuer allowance exceeded")]
+    IssuerAllowanceExceeded,
+    
+    #[msg("Task is not in the required status")]
+    InvalidTaskStatus,
+    
+    #[msg("Signer cannot approve this task")]
+    NotTaskApprover,
+    
+    #[msg("Cannot complete your own task")]
+    SelfDealingNotAllowed,
+    
+    #[msg("Failed to compute reputation commitment")]
+    CommitmentFailed,
+    
+    #[msg("Challenge is not pending")]
+    ChallengeNotPending,
+    
+    #[msg("Challenge must be resolved before withdrawal")]
+    ChallengeUnresolved,
+    
+    #[msg("Dispute is not in the required status")]
+    InvalidDisputeStatus,
+    
+    #[msg("Dispute response window has closed")]
+    ResponseWindowClosed,
+    
+    #[msg("Insufficient treasury funds")]
+    InsufficientTreasuryFunds,
+    
+    #[msg("Proposal instruction is missing")]
+    ProposalInstructionMissing,
+    
+    #[msg("Invalid proposal instruction")]
+    InvalidProposalInstruction,
+    
+    #[msg("No reputation checkpoint before the vote snapshot")]
+    NoReputationAtSnapshot,
+    
+    #[msg("Proposal has not been queued")]
+    ProposalNotQueued,
+    
+    #[msg("Proposal is already queued")]
+    ProposalAlreadyQueued,
+    
+    #[msg("Timelock has not elapsed")]
+    TimelockActive,
+    
+    #[msg("Proposal was vetoed")]
+    ProposalVetoed,
+    
+    #[msg("Veto window has closed")]
+    VetoWindowClosed,
+    
+    #[msg("Signer is not a guardian")]
+    NotGuardian,
+    
+    #[msg("Proposal is not in the required status")]
+    InvalidProposalStatus,
+    
+    #[msg("Proposal execution window has passed")]
+    ProposalExpired,
+    
+    #[msg("Cannot delegate to yourself")]
+    SelfDelegationNotAllowed,
+    
+    #[msg("Invalid delegation accounts")]
+    InvalidDelegation,
+    
+    #[msg("Voting mode not allowed by config")]
+    VotingModeNotAllowed,
+    
+    #[msg("Batch proposal has no attached changes")]
+    ProposalBatchMissing,
+    
+    #[msg("Invalid batch of parameter changes")]
+    InvalidProposalBatch,
+    
+    #[msg("Proposal deposit is not settled")]
+    DepositNotSettled,
+    
+    #[msg("Proposal deposit is already settled")]
+    DepositAlreadySettled,
+    
+    #[msg("Agent metadata field too long")]
+    MetadataTooLong,
+    
+    #[msg("Invalid service endpoint URI")]
+    InvalidServiceEndpoint,
+    
+    #[msg("Invalid session key scope")]
+    InvalidSessionScope,
+    
+    #[msg("Agent has open vouches, disputes or proposals")]
+    AgentHasOpenObligations,
+    
+    #[msg("Agent identity was deregistered and can't be registered again")]
+    AgentDeregistered,
+    
+    #[msg("Agent name is not valid UTF-8")]
+    InvalidNameEncoding,
+    
+    #[msg("Agent name must be 3-50 letters, digits, '-' or '_'")]
+    InvalidAgentName,
+    
+    #[msg("Agent already holds a name")]
+    AgentNameAlreadySet,
+    
+    #[msg("Agent already belongs to an organization")]
+    AlreadyOrgMember,
+    
+    #[msg("Organization still has members")]
+    OrgHasMembers,
+    
+    #[msg("Organization accounts missing or don't match the agent")]
+    InvalidOrganization,
+    
+    #[msg("No verification circuit for this ZK statement")]
+    UnsupportedZKStatement,
+    
+    #[msg("Reputation changed since it was last committed")]
+    ReputationNotCommitted,
 }
@@ -0,0 +1,211 @@
+use anchor_lang::solana_program::alt_bn128::prelude::{
+    alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing,
+};
+
+// Groth16 verifier over BN254 using the alt_bn128 syscalls
+// All points use the uncompressed big-endian EIP-197 encoding:
+//   G1 = x || y (64 bytes), G2 = x_im || x_re || y_im || y_re (128 bytes)
+//
+// Verification key layout (as stored in `ZKVerificationKey::verification_key`):
+//   alpha_g1 (64) || beta_g2 (128) || gamma_g2 (128) || delta_g2 (128) || IC[0..n] (64 each)
+// Proof layout: A (64) || B (128) || C (64)
+
+pub const G1_LEN: usize = 64;
+pub const G2_LEN: usize = 128;
+pub const FIELD_LEN: usize = 32;
+pub const PROOF_LEN: usize = G1_LEN + G2_LEN + G1_LEN;
+pub const VK_HEADER_LEN: usize = G1_LEN + G2_LEN * 3;
+
+/// BN254 base field modulus, used to negate G1 points
+const BASE_FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29,
+    0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d,
+    0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x47,
+];
+
+/// BN254 scalar field modulus, public inputs must be below it
+pub const SCALAR_FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29,
+    0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91,
+    0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x01,
+];
+
+/// Encode a u64 public input as a big-endian field element
+pub fn field_from_u64(value: u64) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[24..].copy_from_slice(&value.to_be_bytes());
+    out
+}
+
+/// Verify a Groth16 proof against a serialized verification key
+/// Returns false for malformed keys, proofs or inputs instead of erroring,
+/// so callers can record a failed verification
+pub fn verify(vk: &[u8], proof: &[u8], public_inputs: &[[u8; 32]]) -> bool {
+    if proof.len() != PROOF_LEN || vk.len() < VK_HEADER_LEN + G1_LEN {
+        return false;
+    }
+    let ic = &vk[VK_HEADER_LEN..];
+    if ic.len() % G1_LEN != 0 || ic.len() / G1_LEN != public_inputs.len() + 1 {
+        return false;
+    }
+    if public_inputs.iter().any(|input| !less_than(input, &SCALAR_FIELD_MODULUS)) {
+        return false;
+    }
+
+    // vk_x = IC[0] + sum(input_i * IC[i + 1])
+    let mut vk_x = [0u8; G1_LEN];
+    vk_x.copy_from_slice(&ic[..G1_LEN]);
+    for (i, input) in public_inputs.iter().enumerate() {
+        let point = &ic[(i + 1) * G1_LEN..(i + 2) * G1_LEN];
+        let mut mul_input = [0u8; G1_LEN + FIELD_LEN];
+        mul_input[..G1_LEN].copy_from_slice(point);
+        mul_input[G1_LEN..].copy_from_slice(input);
+        let Ok(product) = alt_bn128_multiplication(&mul_input) else {
+            return false;
+        };
+
+        let mut add_input = [0u8; G1_LEN * 2];
+        add_input[..G1_LEN].copy_from_slice(&vk_x);
+        add_input[G1_LEN..].copy_from_slice(&product);
+        let Ok(sum) = alt_bn128_addition(&add_input) else {
+            return false;
+        };
+        vk_x.copy_from_slice(&sum);
+    }
+
+    let alpha_g1 = &vk[..G1_LEN];
+    let beta_g2 = &vk[G1_LEN..G1_LEN + G2_LEN];
+    let gamma_g2 = &vk[G1_LEN + G2_LEN..G1_LEN + G2_LEN * 2];
+    let delta_g2 = &vk[G1_LEN + G2_LEN * 2..VK_HEADER_LEN];
+
+    let Some(neg_a) = negate_g1(&proof[..G1_LEN]) else {
+        return false;
+    };
+    let proof_b = &proof[G1_LEN..G1_LEN + G2_LEN];
+    let proof_c = &proof[G1_LEN + G2_LEN..];
+
+    // e(-A, B) * e(alpha, beta) * e(vk_x, gamma) * e(C, delta) == 1
+    let mut pairing_input = Vec::with_capacity((G1_LEN + G2_LEN) * 4);
+    for (g1, g2) in [
+        (&neg_a[..], proof_b),
+        (alpha_g1, beta_g2),
+        (&vk_x[..], gamma_g2),
+        (proof_c, delta_g2),
+    ] {
+        pairing_input.extend_from_slice(g1);
+        pairing_input.extend_from_slice(g2);
+    }
+
+    match alt_bn128_pairing(&pairing_input) {
+        Ok(result) => result.len() == 32 && result[..31].iter().all(|b| *b == 0) && result[31] == 1,
+        Err(_) => false,
+    }
+}
+
+/// Negate a G1 point: (x, y) -> (x, p - y); the identity stays the identity
+fn negate_g1(point: &[u8]) -> Option<[u8; G1_LEN]> {
+    let mut out = [0u8; G1_LEN];
+    out[..32].copy_from_slice(&point[..32]);
+
+    let y = &point[32..64];
+    if y.iter().all(|b| *b == 0) {
+        return Some(out);
+    }
+    let y: [u8; 32] = y.try_into().ok()?;
+    if !less_than(&y, &BASE_FIELD_MODULUS) {
+        return None;
+    }
+
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let mut diff = BASE_FIELD_MODULUS[i] as i16 - y[i] as i16 - borrow;
+        borrow = if diff < 0 { diff += 256; 1 } else { 0 };
+        out[32 + i] = diff as u8;
+    }
+    Some(out)
+}
+
+/// Big-endian comparison of two 32-byte integers
+fn less_than(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    a < b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // circuits/test_vectors/reputation_above: a key shaped like the Above circuit's
+    // (threshold, commitment), with a proof for threshold 1000 and a commitment to 1500
+    const VK: &str = include_str!("../../circuits/test_vectors/reputation_above/vk.hex");
+    const PROOF: &str = include_str!("../../circuits/test_vectors/reputation_above/proof.hex");
+    const PUBLIC_INPUTS: &str = include_str!("../../circuits/test_vectors/reputation_above/public_inputs.hex");
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        let hex = hex.trim();
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn vector() -> (Vec<u8>, Vec<u8>, Vec<[u8; 32]>) {
+        let inputs = PUBLIC_INPUTS
+            .lines()
+            .map(|line| from_hex(line).try_into().unwrap())
+            .collect();
+        (from_hex(VK), from_hex(PROOF), inputs)
+    }
+
+    #[test]
+    fn accepts_the_test_vector() {
+        let (vk, proof, inputs) = vector();
+        assert_eq!(vk.len(), VK_HEADER_LEN + 3 * G1_LEN);
+        assert_eq!(inputs[0], field_from_u64(1000));
+        assert!(verify(&vk, &proof, &inputs));
+    }
+
+    #[test]
+    fn rejects_tampered_public_inputs() {
+        let (vk, proof, mut inputs) = vector();
+        inputs[0] = field_from_u64(1001);
+        assert!(!verify(&vk, &proof, &inputs));
+
+        let (_, _, mut inputs) = vector();
+        inputs[1][31] ^= 1;
+        assert!(!verify(&vk, &proof, &inputs));
+        assert!(!verify(&vk, &proof, &inputs[..1]));
+        assert!(!verify(&vk, &proof, &[inputs[0], SCALAR_FIELD_MODULUS]));
+    }
+
+    #[test]
+    fn rejects_tampered_proofs() {
+        let (vk, proof, inputs) = vector();
+        // Swapping A and C keeps every point on the curve
+        let mut swapped = proof.clone();
+        swapped[..G1_LEN].copy_from_slice(&proof[G1_LEN + G2_LEN..]);
+        swapped[G1_LEN + G2_LEN..].copy_from_slice(&proof[..G1_LEN]);
+        assert!(!verify(&vk, &swapped, &inputs));
+
+        // Negating A flips the pairing check
+        let mut negated = proof.clone();
+        negated[..G1_LEN].copy_from_slice(&negate_g1(&proof[..G1_LEN]).unwrap());
+        assert!(!verify(&vk, &negated, &inputs));
+
+        let mut off_curve = proof.clone();
+        off_curve[G1_LEN - 1] ^= 1;
+        assert!(!verify(&vk, &off_curve, &inputs));
+        assert!(!verify(&vk, &proof[..PROOF_LEN - 1], &inputs));
+    }
+
+    #[test]
+    fn rejects_another_key() {
+        let (mut vk, proof, inputs) = vector();
+        // Use IC[1] as IC[0]
+        let ic = VK_HEADER_LEN;
+        let second = vk[ic + G1_LEN..ic + 2 * G1_LEN].to_vec();
+        vk[ic..ic + G1_LEN].copy_from_slice(&second);
+        assert!(!verify(&vk, &proof, &inputs));
+    }
+}
@@ -1,30 +1,256 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, MintTo, TokenAccount};
-use crate::state::{AgentProfile, TaskRecord, ProtocolConfig};
+use crate::state::{AgentProfile, TaskIssuer, TaskRecord, TaskStatus, ProtocolConfig};
 use crate::errors::ReputationError;
+use crate::instructions::zk_verification::refresh_reputation_commitment;
+use crate::instructions::session::{SessionKey, is_authorized_signer, SCOPE_TASKS};
+
+/// Two-party task flow: an approved issuer posts a task with a reputation budget,
+/// an agent accepts it, and the issuer (or one of its reviewers) approves completion.
+/// Reputation is only minted on approval, never self-reported by the agent.
 
 #[derive(Accounts)]
-pub struct CompleteTask<'info> {
+pub struct RegisterTaskIssuer<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
     
+    /// CHECK: Just the pubkey of the issuer being approved
+    pub issuer: UncheckedAccount<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = TaskIssuer::LEN,
+        seeds = [b"task_issuer", issuer.key().as_ref()],
+        bump
+    )]
+    pub task_issuer: Account<'info, TaskIssuer>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.authority == authority.key()
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn register_task_issuer(ctx: Context<RegisterTaskIssuer>, reputation_allowance: u64) -> Result<()> {
+    let issuer = &mut ctx.accounts.task_issuer;
+    
+    issuer.authority = ctx.accounts.issuer.key();
+    issuer.reputation_allowance = reputation_allowance;
+    issuer.tasks_created = 0;
+    issuer.reviewers = [Pubkey::default(); 5];
+    issuer.reviewer_count = 0;
+    issuer.is_active = true;
+    issuer.bump = ctx.bumps.task_issuer;
+    
+    msg!("Task issuer registered: {} with allowance {}", issuer.authority, reputation_allowance);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AddTaskReviewer<'info> {
+    pub issuer: Signer<'info>,
+    
     #[account(
         mut,
-        seeds = [b"agent", authority.key().as_ref()],
-        bump = agent_profile.bump,
+        seeds = [b"task_issuer", issuer.key().as_ref()],
+        bump = task_issuer.bump,
     )]
-    pub agent_profile: Account<'info, AgentProfile>,
+    pub task_issuer: Account<'info, TaskIssuer>,
+}
+
+pub fn add_task_reviewer(ctx: Context<AddTaskReviewer>, reviewer: Pubkey) -> Result<()> {
+    let issuer = &mut ctx.accounts.task_issuer;
+    let count = issuer.reviewer_count as usize;
+    
+    require!(count < 5, ReputationError::InvalidParameter);
+    require!(!issuer.can_approve(&reviewer), ReputationError::InvalidParameter);
+    
+    issuer.reviewers[count] = reviewer;
+    issuer.reviewer_count += 1;
+    
+    msg!("Reviewer {} added for issuer {}", reviewer, issuer.authority);
+    Ok(())
+}
+
+/// PDA seed for a task id; clamped so an oversized length fails in the handler
+/// with TaskIdTooLong instead of panicking while the seeds are derived
+pub fn task_seed(task_id: &[u8; 100], task_id_len: u8) -> &[u8] {
+    &task_id[..(task_id_len as usize).min(TaskRecord::MAX_TASK_ID_LEN)]
+}
+
+#[derive(Accounts)]
+#[instruction(task_id: [u8; 100], task_id_len: u8)]
+pub struct CreateTask<'info> {
+    #[account(mut)]
+    pub issuer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"task_issuer", issuer.key().as_ref()],
+        bump = task_issuer.bump,
+    )]
+    pub task_issuer: Account<'info, TaskIssuer>,
     
     #[account(
         init,
-        payer = authority,
+        payer = issuer,
         space = TaskRecord::LEN,
-        seeds = [b"task", &task_id[..task_id_len as usize], authority.key().as_ref()],
+        seeds = [b"task", issuer.key().as_ref(), task_seed(&task_id, task_id_len)],
         bump
     )]
     pub task_record: Account<'info, TaskRecord>,
     
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_task(
+    ctx: Context<CreateTask>,
+    task_id: [u8; 100],
+    task_id_len: u8,
+    reputation_budget: u64,
+) -> Result<()> {
+    require!(task_id_len as usize <= TaskRecord::MAX_TASK_ID_LEN, ReputationError::TaskIdTooLong);
+    require!(reputation_budget > 0, ReputationError::InvalidReputationAmount);
+    
+    let issuer = &mut ctx.accounts.task_issuer;
+    require!(issuer.is_active, ReputationError::IssuerInactive);
+    
+    // Reserve the budget up front so an issuer can't promise more than its allowance
+    issuer.reputation_allowance = issuer.reputation_allowance
+        .checked_sub(reputation_budget)
+        .ok_or(ReputationError::IssuerAllowanceExceeded)?;
+    issuer.tasks_created = issuer.tasks_created.saturating_add(1);
+    
+    let clock = Clock::get()?;
+    let task = &mut ctx.accounts.task_record;
+    task.issuer = issuer.authority;
+    task.agent = Pubkey::default();
+    task.task_id = task_id;
+    task.task_id_len = task_id_len;
+    task.reputation_budget = reputation_budget;
+    task.reputation_earned = 0;
+    task.status = TaskStatus::Open;
+    task.approved_by = Pubkey::default();
+    task.created_at = clock.unix_timestamp;
+    task.accepted_at = 0;
+    task.completed_at = 0;
+    task.bump = ctx.bumps.task_record;
+    
+    msg!("Task created by {} with budget {}", task.issuer, reputation_budget);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AcceptTask<'info> {
+    pub agent: Signer<'info>,
+    
+    #[account(
+        seeds = [b"agent", agent_profile.agent_id.as_ref()],
+        bump = agent_profile.bump,
+        constraint = is_authorized_signer(&agent_profile, &agent.key(), session.as_deref(), SCOPE_TASKS)?
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"task", task_record.issuer.as_ref(), &task_record.task_id[..task_record.task_id_len as usize]],
+        bump = task_record.bump,
+    )]
+    pub task_record: Account<'info, TaskRecord>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
+}
+
+pub fn accept_task(ctx: Context<AcceptTask>) -> Result<()> {
+    let agent = ctx.accounts.agent_profile.agent_id;
+    let task = &mut ctx.accounts.task_record;
+    
+    require!(ctx.accounts.agent_profile.is_active, ReputationError::AgentInactive);
+    require!(task.status == TaskStatus::Open, ReputationError::InvalidTaskStatus);
+    require!(
+        agent != task.issuer && ctx.accounts.agent.key() != task.issuer,
+        ReputationError::SelfDealingNotAllowed
+    );
+    
+    task.agent = agent;
+    task.status = TaskStatus::Accepted;
+    task.accepted_at = Clock::get()?.unix_timestamp;
+    
+    msg!("Task accepted by {}", agent);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelTask<'info> {
+    #[account(mut)]
+    pub issuer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"task_issuer", issuer.key().as_ref()],
+        bump = task_issuer.bump,
+    )]
+    pub task_issuer: Account<'info, TaskIssuer>,
+    
     #[account(
+        mut,
+        close = issuer,
+        seeds = [b"task", issuer.key().as_ref(), &task_record.task_id[..task_record.task_id_len as usize]],
+        bump = task_record.bump,
+    )]
+    pub task_record: Account<'info, TaskRecord>,
+}
+
+/// Withdraw a task no agent has accepted, releasing its reserved budget
+pub fn cancel_task(ctx: Context<CancelTask>) -> Result<()> {
+    let task = &ctx.accounts.task_record;
+    require!(task.status == TaskStatus::Open, ReputationError::InvalidTaskStatus);
+    
+    let issuer = &mut ctx.accounts.task_issuer;
+    issuer.reputation_allowance = issuer.reputation_allowance.saturating_add(task.reputation_budget);
+    
+    msg!("Task cancelled by {}, {} allowance released", issuer.authority, task.reputation_budget);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CompleteTask<'info> {
+    #[account(mut)]
+    pub approver: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"task_issuer", task_record.issuer.as_ref()],
+        bump = task_issuer.bump,
+    )]
+    pub task_issuer: Account<'info, TaskIssuer>,
+    
+    #[account(
+        mut,
+        seeds = [b"task", task_record.issuer.as_ref(), &task_record.task_id[..task_record.task_id_len as usize]],
+        bump = task_record.bump,
+    )]
+    pub task_record: Account<'info, TaskRecord>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", agent.key().as_ref()],
+        bump = agent_profile.bump,
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    
+    /// CHECK: Verified against the task record
+    #[account(address = task_record.agent)]
+    pub agent: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
         seeds = [b"config"],
         bump = config.bump,
     )]
@@ -43,25 +269,37 @@ pub struct CompleteTask<'info> {
     )]
     pub reputation_mint: Account<'info, token::Mint>,
     
+    /// Owner's token account; the agent_id itself may hold no key
     #[account(
         mut,
         associated_token::mint = reputation_mint,
-        associated_token::authority = authority,
+        associated_token::authority = agent_profile.owner,
     )]
     pub agent_token_account: Account<'info, TokenAccount>,
     
     pub token_program: Program<'info, token::Token>,
-    pub system_program: Program<'info, System>,
 }
 
-pub fn complete_task(
-    ctx: Context<CompleteTask>,
-    task_id: [u8; 100],
-    task_id_len: u8,
-    reputation_amount: u64,
-) -> Result<()> {
-    require!(task_id_len <= 100, ReputationError::TaskIdTooLong);
-    require!(reputation_amount > 0, ReputationError::InvalidReputationAmount);
+/// Approve a completed task and mint the awarded reputation to the agent
+pub fn complete_task(ctx: Context<CompleteTask>, reputation_amount: u64) -> Result<()> {
+    let approver = ctx.accounts.approver.key();
+    let task = &mut ctx.accounts.task_record;
+    
+    require!(task.status == TaskStatus::Accepted, ReputationError::InvalidTaskStatus);
+    require!(
+        ctx.accounts.task_issuer.can_approve(&approver),
+        ReputationError::NotTaskApprover
+    );
+    require!(
+        approver != task.agent
+            && approver != ctx.accounts.agent_profile.owner
+            && approver != ctx.accounts.agent_profile.operating_key,
+        ReputationError::SelfDealingNotAllowed
+    );
+    require!(
+        reputation_amount > 0 && reputation_amount <= task.reputation_budget,
+        ReputationError::InvalidReputationAmount
+    );
     
     let profile = &mut ctx.accounts.agent_profile;
     let clock = Clock::get()?;
@@ -69,20 +307,26 @@ pub fn complete_task(
     require!(profile.is_active, ReputationError::AgentInactive);
     
     // Update profile
+    let before = profile.reputation_score;
     profile.reputation_score = profile.reputation_score.saturating_add(reputation_amount);
+    ctx.accounts.config.track_reputation_change(before, profile.reputation_score);
     profile.total_tasks_completed = profile.total_tasks_completed.saturating_add(1);
     profile.last_activity_timestamp = clock.unix_timestamp;
+    refresh_reputation_commitment(profile, ctx.accounts.config.last_snapshot_slot)?;
     
-    // Create task record
-    let task = &mut ctx.accounts.task_record;
-    task.agent = ctx.accounts.authority.key();
-    task.task_id = task_id;
+    // Close out task record
     task.reputation_earned = reputation_amount;
+    task.status = TaskStatus::Completed;
+    task.approved_by = approver;
     task.completed_at = clock.unix_timestamp;
-    task.bump = ctx.bumps.task_record;
+    
+    // Return unused budget to the issuer's allowance
+    let unused = task.reputation_budget.saturating_sub(reputation_amount);
+    ctx.accounts.task_issuer.reputation_allowance =
+        ctx.accounts.task_issuer.reputation_allowance.saturating_add(unused);
     
     // Mint reputation tokens to agent
-    let seeds = &[b"mint_authority", &[ctx.bumps.mint_authority]];
+    let seeds = &[b"mint_authority".as_ref(), &[ctx.bumps.mint_authority]];
     let signer = &[&seeds[..]];
     
     token::mint_to(
@@ -98,6 +342,20 @@ pub fn complete_task(
         reputation_amount,
     )?;
     
-    msg!("Task completed: {} | Reputation earned: {}", task_id, reputation_amount);
+    msg!("Task approved by {}: {} earned {} reputation",
+        approver, task.agent, reputation_amount);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_seed_never_exceeds_the_seed_limit() {
+        let task_id = [7u8; 100];
+        assert_eq!(task_seed(&task_id, 5).len(), 5);
+        assert_eq!(task_seed(&task_id, 32).len(), 32);
+        assert_eq!(task_seed(&task_id, 255).len(), TaskRecord::MAX_TASK_ID_LEN);
+    }
+}
@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
 use crate::state::{AgentProfile, ProtocolConfig, calculate_decay};
 use crate::errors::ReputationError;
+use crate::instructions::zk_verification::refresh_reputation_commitment;
 
 #[derive(Accounts)]
 pub struct ApplyDecay<'info> {
@@ -18,6 +19,7 @@ pub struct ApplyDecay<'info> {
     pub agent: UncheckedAccount<'info>,
     
     #[account(
+        mut,
         seeds = [b"config"],
         bump = config.bump,
     )]
@@ -27,7 +29,7 @@ pub struct ApplyDecay<'info> {
 pub fn apply_decay(ctx: Context<ApplyDecay>) -> Result<()> {
     let profile = &mut ctx.accounts.agent_profile;
     let clock = Clock::get()?;
-    let config = &ctx.accounts.config;
+    let config = &mut ctx.accounts.config;
     
     let days_since_activity = (clock.unix_timestamp.saturating_sub(profile.last_activity_timestamp)) / 86400;
     
@@ -41,6 +43,8 @@ pub fn apply_decay(ctx: Context<ApplyDecay>) -> Result<()> {
     );
     
     profile.reputation_score = new_reputation;
+    config.track_reputation_change(old_reputation, new_reputation);
+    refresh_reputation_commitment(profile, config.last_snapshot_slot)?;
     
     // Mark as inactive if reputation drops too low
     if profile.reputation_score < 10 {
@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+use crate::state::AgentProfile;
+use crate::errors::ReputationError;
+use crate::instructions::session::{SessionKey, is_authorized_signer, SCOPE_DELEGATE};
+
+/// Governance vote delegation (liquid democracy)
+/// An agent hands its snapshot vote weight to a delegate (by agent id), who casts it through
+/// vote_proposal and in further batches through cast_delegated_votes. Each delegated vote
+/// still gets its own VoteRecord under the delegator's key, so a delegator's weight is
+/// counted at most once per proposal whether it votes directly or through its delegate.
+/// Delegation is one level deep.
+
+#[account]
+pub struct Delegation {
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+    pub delegated_slot: u64, // Only proposals snapshotted after this slot use the delegation
+    pub delegated_at: i64,
+    pub bump: u8,
+}
+
+impl Delegation {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+#[derive(Accounts)]
+pub struct Delegate<'info> {
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+    
+    #[account(
+        seeds = [b"agent", delegator_profile.agent_id.as_ref()],
+        bump = delegator_profile.bump,
+        constraint = is_authorized_signer(&delegator_profile, &delegator.key(), session.as_deref(), SCOPE_DELEGATE)?
+    )]
+    pub delegator_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        init,
+        payer = delegator,
+        space = Delegation::LEN,
+        seeds = [b"delegation", delegator_profile.agent_id.as_ref()],
+        bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
+}
+
+pub fn delegate(ctx: Context<Delegate>, delegate: Pubkey) -> Result<()> {
+    let delegator = ctx.accounts.delegator_profile.agent_id;
+    require!(delegate != delegator, ReputationError::SelfDelegationNotAllowed);
+    
+    let clock = Clock::get()?;
+    let delegation = &mut ctx.accounts.delegation;
+    delegation.delegator = delegator;
+    delegation.delegate = delegate;
+    delegation.delegated_slot = clock.slot;
+    delegation.delegated_at = clock.unix_timestamp;
+    delegation.bump = ctx.bumps.delegation;
+    
+    msg!("Governance weight of {} delegated to {}", delegator, delegate);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Undelegate<'info> {
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+    
+    #[account(
+        seeds = [b"agent", delegator_profile.agent_id.as_ref()],
+        bump = delegator_profile.bump,
+        constraint = is_authorized_signer(&delegator_profile, &delegator.key(), session.as_deref(), SCOPE_DELEGATE)?
+    )]
+    pub delegator_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        close = delegator,
+        seeds = [b"delegation", delegator_profile.agent_id.as_ref()],
+        bump = delegation.bump,
+    )]
+    pub delegation: Account<'info, Delegation>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
+}
+
+/// Revoke a delegation; votes the delegate already cast stay counted
+pub fn undelegate(ctx: Context<Undelegate>) -> Result<()> {
+    msg!("Delegation from {} to {} revoked",
+        ctx.accounts.delegation.delegator, ctx.accounts.delegation.delegate);
+    Ok(())
+}
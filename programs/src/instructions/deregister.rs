@@ -0,0 +1,279 @@
+use anchor_lang::prelude::*;
+use anchor_lang::{system_program, Discriminator};
+use anchor_spl::token::{self, Burn, CloseAccount, Mint, ThawAccount, Token, TokenAccount, Transfer};
+use crate::state::{AgentProfile, ChallengeStatus, ProtocolConfig, VouchRecord};
+use crate::errors::ReputationError;
+use crate::instructions::name_registry::AgentName;
+use crate::instructions::reputation_nft::ReputationNFT;
+use crate::instructions::vouch::STAKE_ESCROW_SEED;
+use crate::instructions::weighted_vouch::WeightedVouchRecord;
+
+/// Agent deregistration
+/// The owner settles the agent's remaining vouch stakes, retires its soulbound
+/// NFT and closes the profile. A tombstone stays behind under the agent_id so the
+/// identity can't be registered again with a clean history.
+
+#[account]
+pub struct AgentTombstone {
+    pub agent_id: Pubkey,
+    pub owner: Pubkey, // Owner that deregistered the agent
+    pub name: [u8; 50],
+    pub final_reputation: u64,
+    pub total_tasks_completed: u64,
+    pub positive_vouches: u64,
+    pub negative_vouches: u64,
+    pub was_active: bool, // false if slashed or decayed out before leaving
+    pub deregistered_at: i64,
+    pub bump: u8,
+}
+
+impl AgentTombstone {
+    pub const LEN: usize = 8 + 32 + 32 + 50 + 8 + 8 + 8 + 8 + 1 + 8 + 1;
+}
+
+#[derive(Accounts)]
+pub struct DeregisterAgent<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"agent", agent_profile.agent_id.as_ref()],
+        bump = agent_profile.bump,
+        constraint = agent_profile.owner == owner.key()
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = AgentTombstone::LEN,
+        seeds = [b"tombstone", agent_profile.agent_id.as_ref()],
+        bump
+    )]
+    pub tombstone: Account<'info, AgentTombstone>,
+    
+    /// The agent's name record, released for others to claim; required if it holds one
+    #[account(mut)]
+    pub agent_name: Option<Account<'info, AgentName>>,
+    
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    /// Receives the settled vouch stakes
+    #[account(
+        mut,
+        associated_token::mint = config.reputation_mint,
+        associated_token::authority = owner,
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    
+    /// The agent's stake escrow; required if it has anything staked, and closed
+    /// to the owner whenever passed
+    #[account(
+        mut,
+        associated_token::mint = config.reputation_mint,
+        associated_token::authority = stake_escrow,
+    )]
+    pub stake_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: PDA that owns the agent's stake escrow
+    #[account(
+        seeds = [STAKE_ESCROW_SEED, agent_profile.agent_id.as_ref()],
+        bump,
+    )]
+    pub stake_escrow: UncheckedAccount<'info>,
+    
+    /// CHECK: The agent's ReputationNFT record, closed here if one was minted
+    #[account(
+        mut,
+        seeds = [b"reputation_nft", agent_profile.agent_id.as_ref()],
+        bump,
+    )]
+    pub reputation_nft: UncheckedAccount<'info>,
+    
+    /// NFT mint; required if an NFT was minted
+    #[account(mut)]
+    pub nft_mint: Option<Account<'info, Mint>>,
+    
+    /// Token account the NFT was minted to; required if an NFT was minted
+    #[account(mut)]
+    pub nft_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: PDA that acts as mint and freeze authority, and as the NFT token
+    /// account's delegate and close authority
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Deregister an agent (owner only)
+/// remaining_accounts: each VouchRecord or WeightedVouchRecord the agent still
+/// holds as voucher. Each must be out of lockup and not under a pending
+/// challenge; the records are closed and the whole stake escrow is returned to
+/// the owner, along with the escrow account's rent. Fails if any vouch, dispute or proposal is still open afterwards.
+/// The agent's name is released.
+pub fn deregister_agent<'info>(ctx: Context<'_, '_, '_, 'info, DeregisterAgent<'info>>) -> Result<()> {
+    let agent_id = ctx.accounts.agent_profile.agent_id;
+    let lockup = ctx.accounts.config.vouch_lockup_period;
+    let now = Clock::get()?.unix_timestamp;
+    let owner_info = ctx.accounts.owner.to_account_info();
+    
+    for record_info in ctx.remaining_accounts {
+        require!(record_info.owner == ctx.program_id, ReputationError::InvalidParameter);
+        
+        let created_at = {
+            let data = record_info.try_borrow_data()?;
+            if data.starts_with(&VouchRecord::DISCRIMINATOR) {
+                let record = VouchRecord::try_deserialize(&mut &data[..])?;
+                require!(record.voucher == agent_id, ReputationError::InvalidParameter);
+                require!(
+                    record.challenge_status != ChallengeStatus::Pending,
+                    ReputationError::ChallengeUnresolved
+                );
+                record.created_at
+            } else {
+                let record = WeightedVouchRecord::try_deserialize(&mut &data[..])?;
+                require!(record.voucher == agent_id, ReputationError::InvalidParameter);
+                record.created_at
+            }
+        };
+        require!(now.saturating_sub(created_at) >= lockup, ReputationError::LockupNotExpired);
+        
+        close_program_account(record_info, &owner_info)?;
+        ctx.accounts.agent_profile.open_vouches =
+            ctx.accounts.agent_profile.open_vouches.saturating_sub(1);
+    }
+    
+    // Every vouch is settled, so the whole stake goes back to the owner and the
+    // escrow account is closed to them
+    let returned = ctx.accounts.agent_profile.release_stake(u64::MAX);
+    require!(
+        returned == 0 || ctx.accounts.stake_token_account.is_some(),
+        ReputationError::InvalidParameter
+    );
+    if let Some(stake_token_account) = ctx.accounts.stake_token_account.as_ref() {
+        let seeds = &[STAKE_ESCROW_SEED, agent_id.as_ref(), &[ctx.bumps.stake_escrow]];
+        let signer = &[&seeds[..]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: stake_token_account.to_account_info(),
+                    to: ctx.accounts.owner_token_account.to_account_info(),
+                    authority: ctx.accounts.stake_escrow.to_account_info(),
+                },
+                signer,
+            ),
+            stake_token_account.amount,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: stake_token_account.to_account_info(),
+                destination: ctx.accounts.owner.to_account_info(),
+                authority: ctx.accounts.stake_escrow.to_account_info(),
+            },
+            signer,
+        ))?;
+    }
+    
+    let profile = &ctx.accounts.agent_profile;
+    require!(
+        profile.open_vouches == 0 && profile.open_disputes == 0 && profile.open_proposals == 0,
+        ReputationError::AgentHasOpenObligations
+    );
+    require!(profile.organization == Pubkey::default(), ReputationError::AlreadyOrgMember);
+    
+    // Retire the soulbound NFT: the mint authority thaws, burns and closes the
+    // token wherever it was minted, and the record goes with it
+    if !ctx.accounts.reputation_nft.data_is_empty() {
+        let nft = {
+            let data = ctx.accounts.reputation_nft.try_borrow_data()?;
+            ReputationNFT::try_deserialize(&mut &data[..])?
+        };
+        let nft_mint = ctx.accounts.nft_mint.as_ref().ok_or(ReputationError::InvalidParameter)?;
+        let nft_token_account = ctx.accounts.nft_token_account.as_ref()
+            .ok_or(ReputationError::InvalidParameter)?;
+        require!(
+            nft_mint.key() == nft.mint && nft_token_account.mint == nft.mint,
+            ReputationError::InvalidParameter
+        );
+        
+        let seeds = &[b"mint_authority".as_ref(), &[ctx.bumps.mint_authority]];
+        let signer = &[&seeds[..]];
+        token::thaw_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            ThawAccount {
+                account: nft_token_account.to_account_info(),
+                mint: nft_mint.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            signer,
+        ))?;
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: nft_mint.to_account_info(),
+                    from: nft_token_account.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                signer,
+            ),
+            nft_token_account.amount,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: nft_token_account.to_account_info(),
+                destination: ctx.accounts.owner.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            signer,
+        ))?;
+        close_program_account(&ctx.accounts.reputation_nft.to_account_info(), &owner_info)?;
+    }
+    
+    if ctx.accounts.agent_profile.name_len > 0 {
+        let agent_name = ctx.accounts.agent_name.as_ref().ok_or(ReputationError::InvalidParameter)?;
+        require!(agent_name.agent_id == agent_id, ReputationError::InvalidParameter);
+        agent_name.close(owner_info.clone())?;
+    }
+    
+    let profile = &ctx.accounts.agent_profile;
+    let tombstone = &mut ctx.accounts.tombstone;
+    tombstone.agent_id = agent_id;
+    tombstone.owner = profile.owner;
+    tombstone.name = profile.name;
+    tombstone.final_reputation = profile.reputation_score;
+    tombstone.total_tasks_completed = profile.total_tasks_completed;
+    tombstone.positive_vouches = profile.positive_vouches;
+    tombstone.negative_vouches = profile.negative_vouches;
+    tombstone.was_active = profile.is_active;
+    tombstone.deregistered_at = now;
+    tombstone.bump = ctx.bumps.tombstone;
+    
+    // The profile's reputation leaves the network total with it
+    ctx.accounts.config.track_reputation_change(profile.reputation_score, 0);
+    
+    msg!("Agent {} deregistered: returned {} staked tokens", agent_id, returned);
+    Ok(())
+}
+
+/// Close a program-owned account passed outside the Accounts struct
+pub(crate) fn close_program_account<'info>(info: &AccountInfo<'info>, destination: &AccountInfo<'info>) -> Result<()> {
+    let lamports = destination.lamports().saturating_add(info.lamports());
+    **destination.try_borrow_mut_lamports()? = lamports;
+    **info.try_borrow_mut_lamports()? = 0;
+    info.assign(&system_program::ID);
+    info.realloc(0, false)?;
+    Ok(())
+}
@@ -0,0 +1,647 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, TokenAccount, Transfer};
+use crate::state::{AgentProfile, ProtocolConfig, EvidenceCategory};
+use crate::errors::ReputationError;
+use crate::instructions::treasury::{Treasury, deposit_to_treasury};
+use crate::instructions::vouch::STAKE_ESCROW_SEED;
+use crate::instructions::zk_verification::refresh_reputation_commitment;
+use crate::instructions::session::{SessionKey, is_authorized_signer, SCOPE_DISPUTE};
+use crate::instructions::organization::{Organization, OrgMembership, ORG_STAKE_ESCROW_SEED, verify_org_stake_escrow};
+
+// Dispute resolution for slashing
+// A challenger opens a dispute with evidence and a lamport bond, the target gets a
+// response window, then jurors vote with reputation weight. The slash only lands
+// when an upheld dispute is executed; the losing side's bond goes to the winner.
+
+pub const DISPUTE_BOND_LAMPORTS: u64 = 100_000_000; // 0.1 SOL
+pub const DISPUTE_RESPONSE_WINDOW: i64 = 86400 * 2; // 2 days for the target to respond
+pub const DISPUTE_VOTING_PERIOD: i64 = 86400 * 3; // 3 days of juror voting
+pub const MIN_REPUTATION_TO_DISPUTE: u64 = 5000;
+pub const MIN_REPUTATION_FOR_JUROR: u64 = 1000;
+pub const SLASH_BOUNTY_BPS: u64 = 500; // Challenger's 5% of the reputation slashed
+pub const MIN_REPUTATION_TO_STAY_ACTIVE: u64 = 100; // Slashed below this, the agent is deactivated
+
+#[account]
+pub struct Dispute {
+    pub id: u64, // Target's dispute nonce, used in the PDA seeds
+    pub challenger: Pubkey,
+    pub target: Pubkey,
+    pub evidence_hash: [u8; 32],
+    pub category: EvidenceCategory, // Picks the configured slash rate
+    pub response_hash: [u8; 32], // Target's counter-evidence, zero until responded
+    pub challenger_bond: u64,
+    pub target_bond: u64,
+    pub votes_uphold: u64,
+    pub votes_dismiss: u64,
+    pub status: DisputeStatus,
+    pub created_at: i64,
+    pub response_deadline: i64,
+    pub voting_ends_at: i64,
+    pub snapshot_slot: u64, // Juror weights are reputation as of this slot
+    pub bump: u8,
+}
+
+impl Dispute {
+    pub const LEN: usize = 8 + 8 + 32 + 32 + 32 + 1 + 32 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DisputeStatus {
+    Open,      // Waiting for the target's response
+    Voting,    // Jurors voting (target responded or window lapsed)
+    Upheld,    // Slash executed
+    Dismissed, // Target cleared
+}
+
+#[account]
+pub struct JurorVote {
+    pub juror: Pubkey,
+    pub dispute: Pubkey,
+    pub weight: u64,
+    pub uphold: bool,
+    pub voted_at: i64,
+    pub bump: u8,
+}
+
+impl JurorVote {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1 + 8 + 1;
+}
+
+#[derive(Accounts)]
+pub struct OpenDispute<'info> {
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", challenger_profile.agent_id.as_ref()],
+        bump = challenger_profile.bump,
+        constraint = is_authorized_signer(&challenger_profile, &challenger.key(), session.as_deref(), SCOPE_DISPUTE)?
+    )]
+    pub challenger_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", target.key().as_ref()],
+        bump = target_profile.bump,
+    )]
+    pub target_profile: Account<'info, AgentProfile>,
+    
+    /// CHECK: The agent being disputed
+    pub target: UncheckedAccount<'info>,
+    
+    /// Holds both bonds until the dispute is settled
+    #[account(
+        init,
+        payer = challenger,
+        space = Dispute::LEN,
+        seeds = [b"dispute", target.key().as_ref(), &target_profile.dispute_count.to_le_bytes()],
+        bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+    
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
+}
+
+/// Open a slashing dispute, posting categorised evidence and a bond
+pub fn open_dispute(
+    ctx: Context<OpenDispute>,
+    evidence_hash: [u8; 32],
+    category: EvidenceCategory,
+) -> Result<()> {
+    let challenger = ctx.accounts.challenger_profile.agent_id;
+    let target = ctx.accounts.target.key();
+    
+    require!(challenger != target, ReputationError::SelfDealingNotAllowed);
+    require!(
+        ctx.accounts.challenger_profile.reputation_score >= MIN_REPUTATION_TO_DISPUTE,
+        ReputationError::InsufficientReputation
+    );
+    
+    // Bond is held in the dispute account itself
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.challenger.to_account_info(),
+                to: ctx.accounts.dispute.to_account_info(),
+            },
+        ),
+        DISPUTE_BOND_LAMPORTS,
+    )?;
+    
+    ctx.accounts.challenger_profile.open_disputes = 
+        ctx.accounts.challenger_profile.open_disputes.saturating_add(1);
+    ctx.accounts.target_profile.open_disputes = 
+        ctx.accounts.target_profile.open_disputes.saturating_add(1);
+    
+    let clock = Clock::get()?;
+    let dispute = &mut ctx.accounts.dispute;
+    dispute.id = ctx.accounts.target_profile.dispute_count;
+    ctx.accounts.target_profile.dispute_count = dispute.id.saturating_add(1);
+    dispute.challenger = challenger;
+    dispute.target = target;
+    dispute.evidence_hash = evidence_hash;
+    dispute.category = category;
+    dispute.response_hash = [0u8; 32];
+    dispute.challenger_bond = DISPUTE_BOND_LAMPORTS;
+    dispute.target_bond = 0;
+    dispute.votes_uphold = 0;
+    dispute.votes_dismiss = 0;
+    dispute.status = DisputeStatus::Open;
+    dispute.created_at = clock.unix_timestamp;
+    dispute.response_deadline = clock.unix_timestamp + DISPUTE_RESPONSE_WINDOW;
+    dispute.voting_ends_at = dispute.response_deadline + DISPUTE_VOTING_PERIOD;
+    dispute.snapshot_slot = clock.slot;
+    dispute.bump = ctx.bumps.dispute;
+    ctx.accounts.config.take_snapshot(dispute.snapshot_slot);
+    
+    msg!("Dispute opened by {} against {}", challenger, target);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RespondToDispute<'info> {
+    #[account(mut)]
+    pub target: Signer<'info>,
+    
+    #[account(
+        seeds = [b"agent", target_profile.agent_id.as_ref()],
+        bump = target_profile.bump,
+        constraint = is_authorized_signer(&target_profile, &target.key(), session.as_deref(), SCOPE_DISPUTE)?
+    )]
+    pub target_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"dispute", target_profile.agent_id.as_ref(), &dispute.id.to_le_bytes()],
+        bump = dispute.bump,
+    )]
+    pub dispute: Account<'info, Dispute>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
+}
+
+/// Target posts counter-evidence and a matching bond, opening juror voting early
+pub fn respond_to_dispute(ctx: Context<RespondToDispute>, response_hash: [u8; 32]) -> Result<()> {
+    let clock = Clock::get()?;
+    
+    require!(
+        ctx.accounts.dispute.status == DisputeStatus::Open,
+        ReputationError::InvalidDisputeStatus
+    );
+    require!(
+        clock.unix_timestamp < ctx.accounts.dispute.response_deadline,
+        ReputationError::ResponseWindowClosed
+    );
+    
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.target.to_account_info(),
+                to: ctx.accounts.dispute.to_account_info(),
+            },
+        ),
+        DISPUTE_BOND_LAMPORTS,
+    )?;
+    
+    let dispute = &mut ctx.accounts.dispute;
+    dispute.response_hash = response_hash;
+    dispute.target_bond = DISPUTE_BOND_LAMPORTS;
+    dispute.status = DisputeStatus::Voting;
+    dispute.voting_ends_at = clock.unix_timestamp + DISPUTE_VOTING_PERIOD;
+    
+    msg!("Dispute response submitted by {}", dispute.target);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct VoteDispute<'info> {
+    #[account(mut)]
+    pub juror: Signer<'info>,
+    
+    #[account(
+        seeds = [b"agent", juror_profile.agent_id.as_ref()],
+        bump = juror_profile.bump,
+        constraint = is_authorized_signer(&juror_profile, &juror.key(), session.as_deref(), SCOPE_DISPUTE)?
+    )]
+    pub juror_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"dispute", dispute.target.as_ref(), &dispute.id.to_le_bytes()],
+        bump = dispute.bump,
+    )]
+    pub dispute: Account<'info, Dispute>,
+    
+    #[account(
+        init,
+        payer = juror,
+        space = JurorVote::LEN,
+        seeds = [b"juror_vote", dispute.key().as_ref(), juror_profile.agent_id.as_ref()],
+        bump
+    )]
+    pub juror_vote: Account<'info, JurorVote>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
+}
+
+/// Juror vote weighted by reputation at the dispute's snapshot, so reputation
+/// gained after it opened doesn't count; the parties can't sit on their own jury
+pub fn vote_dispute(ctx: Context<VoteDispute>, uphold: bool) -> Result<()> {
+    let juror = ctx.accounts.juror_profile.agent_id;
+    let dispute = &mut ctx.accounts.dispute;
+    let weight = ctx.accounts.juror_profile
+        .reputation_at(dispute.snapshot_slot)
+        .ok_or(ReputationError::NoReputationAtSnapshot)?;
+    let clock = Clock::get()?;
+    
+    require!(
+        juror != dispute.challenger && juror != dispute.target,
+        ReputationError::SelfDealingNotAllowed
+    );
+    require!(weight >= MIN_REPUTATION_FOR_JUROR, ReputationError::InsufficientReputation);
+    
+    // The response window lapsing without a response moves the dispute to voting
+    if dispute.status == DisputeStatus::Open && clock.unix_timestamp >= dispute.response_deadline {
+        dispute.status = DisputeStatus::Voting;
+    }
+    require!(dispute.status == DisputeStatus::Voting, ReputationError::InvalidDisputeStatus);
+    require!(clock.unix_timestamp < dispute.voting_ends_at, ReputationError::VotingPeriodEnded);
+    
+    if uphold {
+        dispute.votes_uphold = dispute.votes_uphold.saturating_add(weight);
+    } else {
+        dispute.votes_dismiss = dispute.votes_dismiss.saturating_add(weight);
+    }
+    
+    let vote = &mut ctx.accounts.juror_vote;
+    vote.juror = juror;
+    vote.dispute = dispute.key();
+    vote.weight = weight;
+    vote.uphold = uphold;
+    vote.voted_at = clock.unix_timestamp;
+    vote.bump = ctx.bumps.juror_vote;
+    
+    msg!("Juror {} voted {} with weight {}",
+        juror, if uphold { "UPHOLD" } else { "DISMISS" }, weight);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SlashAgent<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"dispute", target.key().as_ref(), &dispute.id.to_le_bytes()],
+        bump = dispute.bump,
+        constraint = dispute.challenger == challenger.key()
+    )]
+    pub dispute: Account<'info, Dispute>,
+    
+    /// CHECK: Verified by the dispute seeds
+    pub challenger: UncheckedAccount<'info>,
+    
+    /// CHECK: Challenger's owner, receives the bonds if upheld
+    #[account(mut, address = challenger_profile.owner)]
+    pub challenger_owner: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", challenger.key().as_ref()],
+        bump = challenger_profile.bump,
+    )]
+    pub challenger_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", target.key().as_ref()],
+        bump = target_profile.bump,
+    )]
+    pub target_profile: Account<'info, AgentProfile>,
+    
+    /// CHECK: Verified by the dispute seeds
+    pub target: UncheckedAccount<'info>,
+    
+    /// CHECK: Target's owner, receives the bonds if dismissed
+    #[account(mut, address = target_profile.owner)]
+    pub target_owner: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    /// Challenger owner's token account, paid the bounty
+    #[account(
+        mut,
+        associated_token::mint = config.reputation_mint,
+        associated_token::authority = challenger_profile.owner,
+    )]
+    pub challenger_token_account: Account<'info, TokenAccount>,
+    
+    /// Target's stake escrow; the stake slash and bounty come out of it.
+    /// Created empty if the target never staked
+    #[account(
+        init_if_needed,
+        payer = executor,
+        associated_token::mint = reputation_mint,
+        associated_token::authority = target_stake_escrow,
+    )]
+    pub target_stake_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: PDA that owns the target's stake escrow
+    #[account(
+        seeds = [STAKE_ESCROW_SEED, target.key().as_ref()],
+        bump,
+    )]
+    pub target_stake_escrow: UncheckedAccount<'info>,
+    
+    #[account(address = config.reputation_mint)]
+    pub reputation_mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(
+        mut,
+        address = treasury.token_account
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    
+    /// Target's organization and membership, required when the target belongs to one
+    #[account(mut)]
+    pub target_organization: Option<Account<'info, Organization>>,
+    
+    #[account(mut)]
+    pub target_membership: Option<Account<'info, OrgMembership>>,
+    
+    /// Org stake escrow, required when the organization shares liability
+    #[account(mut)]
+    pub org_stake_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Verified against the organization in the handler
+    pub org_stake_escrow: Option<UncheckedAccount<'info>>,
+    
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// What an upheld dispute takes from the target
+#[derive(Debug, PartialEq, Eq)]
+pub struct SlashOutcome {
+    pub slash_amount: u64, // Reputation removed
+    pub stake_slashed: u64, // Stake tokens taken from the target's escrow
+    pub bounty: u64, // Challenger's reward, paid out of the slashed stake
+    pub forfeited: u64, // Rest of the slashed stake, for the treasury
+}
+
+/// Slash `slash_bps` of the target's reputation and of its whole stake
+/// Higher reputation agents lose more; the challenger's bounty is a share of the
+/// reputation slashed, capped at the stake slashed alongside it
+pub fn apply_slash(target_profile: &mut AgentProfile, slash_bps: u64) -> SlashOutcome {
+    let slash_amount = target_profile.reputation_score
+        .saturating_mul(slash_bps)
+        .saturating_div(10000);
+    target_profile.reputation_score = target_profile.reputation_score.saturating_sub(slash_amount);
+    
+    // Deactivate if reputation too low
+    if target_profile.reputation_score < MIN_REPUTATION_TO_STAY_ACTIVE {
+        target_profile.is_active = false;
+    }
+    
+    let stake_slashed = target_profile.slash_stake(slash_bps);
+    let bounty = slash_amount
+        .saturating_mul(SLASH_BOUNTY_BPS)
+        .saturating_div(10000)
+        .min(stake_slashed);
+    SlashOutcome {
+        slash_amount,
+        stake_slashed,
+        bounty,
+        forfeited: stake_slashed - bounty,
+    }
+}
+
+/// Execute or dismiss a dispute once juror voting has closed
+/// Anyone can crank it; the slash only lands if jurors upheld the dispute
+pub fn slash_agent(ctx: Context<SlashAgent>) -> Result<()> {
+    let clock = Clock::get()?;
+    let dispute = &mut ctx.accounts.dispute;
+    
+    require!(
+        dispute.status == DisputeStatus::Open || dispute.status == DisputeStatus::Voting,
+        ReputationError::InvalidDisputeStatus
+    );
+    require!(clock.unix_timestamp >= dispute.voting_ends_at, ReputationError::VotingPeriodActive);
+    
+    ctx.accounts.challenger_profile.open_disputes = 
+        ctx.accounts.challenger_profile.open_disputes.saturating_sub(1);
+    ctx.accounts.target_profile.open_disputes = 
+        ctx.accounts.target_profile.open_disputes.saturating_sub(1);
+    
+    // Ties and empty juries favour the target
+    let upheld = dispute.votes_uphold > dispute.votes_dismiss;
+    let bonds = dispute.challenger_bond.saturating_add(dispute.target_bond);
+    
+    if upheld {
+        let target_profile = &mut ctx.accounts.target_profile;
+        
+        // Rate scales with severity
+        let slash_percentage = ctx.accounts.config.slash_rate(dispute.category);
+        let before = target_profile.reputation_score;
+        let SlashOutcome { slash_amount, stake_slashed, bounty, forfeited } =
+            apply_slash(target_profile, slash_percentage);
+        ctx.accounts.config.track_reputation_change(before, target_profile.reputation_score);
+        refresh_reputation_commitment(target_profile, ctx.accounts.config.last_snapshot_slot)?;
+        
+        let target_key = ctx.accounts.target.key();
+        let seeds = &[STAKE_ESCROW_SEED, target_key.as_ref(), &[ctx.bumps.target_stake_escrow]];
+        let signer = &[&seeds[..]];
+        
+        if bounty > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.target_stake_token_account.to_account_info(),
+                        to: ctx.accounts.challenger_token_account.to_account_info(),
+                        authority: ctx.accounts.target_stake_escrow.to_account_info(),
+                    },
+                    signer,
+                ),
+                bounty,
+            )?;
+        }
+        deposit_to_treasury(
+            &mut ctx.accounts.treasury,
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.target_stake_token_account.to_account_info(),
+                    to: ctx.accounts.treasury_token_account.to_account_info(),
+                    authority: ctx.accounts.target_stake_escrow.to_account_info(),
+                },
+                signer,
+            ),
+            forfeited,
+        )?;
+        
+        // Record the slash against the target's organization, which also loses
+        // liability_bps of the same share of its stake
+        let mut org_stake_slashed = 0;
+        if target_profile.organization != Pubkey::default() {
+            let (Some(org), Some(membership)) = (
+                ctx.accounts.target_organization.as_mut(),
+                ctx.accounts.target_membership.as_mut(),
+            ) else {
+                return err!(ReputationError::InvalidOrganization);
+            };
+            require!(
+                org.key() == target_profile.organization
+                    && membership.organization == org.key()
+                    && membership.agent_id == target_profile.agent_id,
+                ReputationError::InvalidOrganization
+            );
+            
+            org.slash_count = org.slash_count.saturating_add(1);
+            org.reputation_slashed = org.reputation_slashed.saturating_add(slash_amount);
+            org.last_slashed_at = clock.unix_timestamp;
+            membership.slash_count = membership.slash_count.saturating_add(1);
+            org.sync_member(membership, target_profile);
+            
+            org_stake_slashed = org.stake_liability(slash_percentage);
+            if org_stake_slashed > 0 {
+                let (Some(stake_token_account), Some(stake_escrow)) = (
+                    ctx.accounts.org_stake_token_account.as_ref(),
+                    ctx.accounts.org_stake_escrow.as_ref(),
+                ) else {
+                    return err!(ReputationError::InvalidOrganization);
+                };
+                let org_key = org.key();
+                let escrow_bump = verify_org_stake_escrow(
+                    ctx.program_id,
+                    &org_key,
+                    stake_escrow,
+                    stake_token_account,
+                    &ctx.accounts.config.reputation_mint,
+                )?;
+                let seeds = &[ORG_STAKE_ESCROW_SEED, org_key.as_ref(), &[escrow_bump]];
+                let signer = &[&seeds[..]];
+                
+                deposit_to_treasury(
+                    &mut ctx.accounts.treasury,
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: stake_token_account.to_account_info(),
+                            to: ctx.accounts.treasury_token_account.to_account_info(),
+                            authority: stake_escrow.to_account_info(),
+                        },
+                        signer,
+                    ),
+                    org_stake_slashed,
+                )?;
+                org.stake = org.stake.saturating_sub(org_stake_slashed);
+                org.stake_slashed = org.stake_slashed.saturating_add(org_stake_slashed);
+            }
+        }
+        
+        dispute.status = DisputeStatus::Upheld;
+        msg!("Agent {} slashed by {}: lost {} reputation, {} stake (bounty paid: {}, org stake: {})",
+            dispute.target, dispute.challenger, slash_amount, stake_slashed, bounty, org_stake_slashed);
+    } else {
+        dispute.status = DisputeStatus::Dismissed;
+        msg!("Dispute against {} dismissed", dispute.target);
+    }
+    
+    // Losing side's bond goes to the winner along with their own
+    let winner = if upheld {
+        ctx.accounts.challenger_owner.to_account_info()
+    } else {
+        ctx.accounts.target_owner.to_account_info()
+    };
+    dispute.challenger_bond = 0;
+    dispute.target_bond = 0;
+    **dispute.to_account_info().try_borrow_mut_lamports()? -= bonds;
+    **winner.try_borrow_mut_lamports()? += bonds;
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(reputation: u64, staked: u64) -> AgentProfile {
+        let mut profile = AgentProfile::deserialize(&mut &[0u8; AgentProfile::LEN][..]).unwrap();
+        profile.reputation_score = reputation;
+        profile.staked_amount = staked;
+        profile.is_active = true;
+        profile
+    }
+
+    #[test]
+    fn slash_takes_the_same_share_of_reputation_and_stake() {
+        let mut target = profile(10_000, 4_000);
+        let outcome = apply_slash(&mut target, 2000);
+        assert_eq!(outcome, SlashOutcome {
+            slash_amount: 2_000,
+            stake_slashed: 800,
+            bounty: 100,
+            forfeited: 700,
+        });
+        assert_eq!(target.reputation_score, 8_000);
+        assert_eq!(target.staked_amount, 3_200);
+        assert!(target.is_active);
+    }
+
+    #[test]
+    fn bounty_is_capped_by_the_slashed_stake() {
+        let mut target = profile(10_000, 0);
+        let outcome = apply_slash(&mut target, 2000);
+        assert_eq!(outcome.bounty, 0);
+        assert_eq!(outcome.forfeited, 0);
+
+        let mut target = profile(10_000, 250);
+        let outcome = apply_slash(&mut target, 2000);
+        assert_eq!(outcome.stake_slashed, 50);
+        assert_eq!(outcome.bounty, 50);
+        assert_eq!(outcome.forfeited, 0);
+    }
+
+    #[test]
+    fn slashing_below_the_floor_deactivates() {
+        let mut target = profile(110, 0);
+        apply_slash(&mut target, 5000);
+        assert_eq!(target.reputation_score, 55);
+        assert!(!target.is_active);
+    }
+}
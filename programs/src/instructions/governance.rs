@@ -1,34 +1,293 @@
 use anchor_lang::prelude::*;
-use crate::state::ProtocolConfig;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::system_program::{self, Allocate, Assign, CreateAccount};
+use anchor_spl::token::{self, TokenAccount, Transfer};
+use crate::state::{AgentProfile, ProtocolConfig};
 use crate::errors::ReputationError;
+use crate::instructions::treasury::{Treasury, deposit_to_treasury};
+use crate::instructions::oracle::OracleRegistry;
+use crate::instructions::zk_verification::ZKVerificationKey;
+use crate::instructions::delegation::Delegation;
+use crate::instructions::deregister::close_program_account;
+use crate::instructions::session::{SessionKey, is_authorized_signer, SCOPE_PROPOSE, SCOPE_VOTE};
 
 /// DAO Governance for dynamic parameter updates
 /// Allows reputation-weighted voting on protocol parameters
 
 #[account]
 pub struct GovernanceProposal {
+    pub id: u64, // Sequential, from config.proposal_count
     pub proposer: Pubkey,
     pub proposal_type: ProposalType,
     pub new_value: u64,
+    pub recipient: Pubkey, // TreasurySpend payee, default otherwise
+    pub deposit: u64, // Escrowed until settle_proposal_deposit, 0 once settled
+    pub deposit_settled: bool, // Set by settle_proposal_deposit, even for a zero deposit
+    pub deposit_from: Pubkey, // Token account that paid the deposit, refunded there
     pub description: [u8; 200], // Fixed-size description
-    pub votes_for: u64,
+    pub votes_for: u64, // Tallies are in voting_mode weight, before any conviction accrual
     pub votes_against: u64,
+    pub votes_abstain: u64, // Counts toward quorum only
+    pub vote_seconds: [u128; 3], // Per choice, sum of weight * (voted_at - snapshot_timestamp)
+    pub participation: u64, // Raw snapshot reputation that voted, checked against quorum
+    pub voting_mode: VotingMode,
     pub voting_ends_at: i64,
-    pub executed: bool,
+    pub snapshot_slot: u64, // Vote weights are reputation as of this slot
+    pub snapshot_timestamp: i64,
+    pub quorum: u64, // Fixed at creation from the config
+    pub majority_bps: u64, // Fixed at creation from the config
+    pub queued_at: i64, // 0 until queued
+    pub eta: i64, // Earliest execution time once queued
+    pub vetoed_by: Pubkey, // Guardian that vetoed, default otherwise
+    pub vetoed_at: i64,
+    pub executed_at: i64,
+    pub finalized_at: i64, // When the vote outcome or expiry was recorded
+    pub cancelled_at: i64,
+    pub status: ProposalStatus,
     pub bump: u8,
 }
 
 impl GovernanceProposal {
-    pub const LEN: usize = 8 + 32 + 1 + 8 + 200 + 8 + 8 + 8 + 1 + 1;
+    pub const LEN: usize = 8 + 8 + 32 + 1 + 8 + 32 + 8 + 1 + 32 + 200 + 8 + 8 + 8 + (3 * 16) + 8 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 32 + 8 + 8 + 8 + 8 + 1 + 1;
+    
+    /// Quorum (in raw reputation) as fixed at creation, and mode-weighted votes for
+    /// above majority_bps of mode-weighted votes cast, as tallied at `now`
+    pub fn passed(&self, now: i64) -> bool {
+        let votes_for = self.tally(VoteChoice::For, now);
+        let total_votes = votes_for.saturating_add(self.tally(VoteChoice::Against, now));
+        self.participation >= self.quorum
+            && (votes_for as u128) * 10000 > (total_votes as u128) * (self.majority_bps as u128)
+    }
+    
+    pub fn add_votes(&mut self, choice: VoteChoice, weight: u64, voted_at: i64) {
+        let seconds = (weight as u128) * self.seconds_into_vote(voted_at);
+        let (tally, tally_seconds) = self.tally_mut(choice);
+        *tally = tally.saturating_add(weight);
+        *tally_seconds = tally_seconds.saturating_add(seconds);
+    }
+    
+    /// Undo an add_votes made with the same weight and voted_at
+    pub fn remove_votes(&mut self, choice: VoteChoice, weight: u64, voted_at: i64) {
+        let seconds = (weight as u128) * self.seconds_into_vote(voted_at);
+        let (tally, tally_seconds) = self.tally_mut(choice);
+        *tally = tally.saturating_sub(weight);
+        *tally_seconds = tally_seconds.saturating_sub(seconds);
+    }
+    
+    /// Weight for `choice` as of `at`. Conviction votes accrue from their voted_at,
+    /// growing from 1x towards CONVICTION_MAX_MULTIPLIER_BPS for a vote held through
+    /// the whole period, and stop accruing when voting ends
+    pub fn tally(&self, choice: VoteChoice, at: i64) -> u64 {
+        let (tally, seconds) = match choice {
+            VoteChoice::For => (self.votes_for, self.vote_seconds[0]),
+            VoteChoice::Against => (self.votes_against, self.vote_seconds[1]),
+            VoteChoice::Abstain => (self.votes_abstain, self.vote_seconds[2]),
+        };
+        if self.voting_mode != VotingMode::Conviction {
+            return tally;
+        }
+        let period = self.voting_ends_at.saturating_sub(self.snapshot_timestamp).max(1) as u128;
+        let elapsed = self.seconds_into_vote(at.min(self.voting_ends_at));
+        let held = (tally as u128).saturating_mul(elapsed).saturating_sub(seconds);
+        let bonus = held.saturating_mul((CONVICTION_MAX_MULTIPLIER_BPS - 10000) as u128) / period / 10000;
+        (tally as u128).saturating_add(bonus).min(u64::MAX as u128) as u64
+    }
+    
+    fn seconds_into_vote(&self, at: i64) -> u128 {
+        at.saturating_sub(self.snapshot_timestamp).max(0) as u128
+    }
+    
+    fn tally_mut(&mut self, choice: VoteChoice) -> (&mut u64, &mut u128) {
+        match choice {
+            VoteChoice::For => (&mut self.votes_for, &mut self.vote_seconds[0]),
+            VoteChoice::Against => (&mut self.votes_against, &mut self.vote_seconds[1]),
+            VoteChoice::Abstain => (&mut self.votes_abstain, &mut self.vote_seconds[2]),
+        }
+    }
+    
+    /// Base weight counted in the tallies for `reputation`; conviction accrues on
+    /// top of it at tally time
+    pub fn vote_weight(&self, reputation: u64) -> u64 {
+        match self.voting_mode {
+            VotingMode::Linear | VotingMode::Conviction => reputation,
+            VotingMode::Quadratic => integer_sqrt(reputation),
+        }
+    }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum VoteChoice {
+    For,
+    Against,
+    Abstain, // Counts toward quorum but not majority
+}
+
+impl VoteChoice {
+    pub fn label(&self) -> &'static str {
+        match self {
+            VoteChoice::For => "FOR",
+            VoteChoice::Against => "AGAINST",
+            VoteChoice::Abstain => "ABSTAIN",
+        }
+    }
+}
+
+/// How vote weight is derived from snapshot reputation
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum VotingMode {
+    Linear,     // weight = reputation
+    Quadratic,  // weight = sqrt(reputation)
+    Conviction, // weight = reputation, growing the longer the vote is held unchanged
+}
+
+impl VotingMode {
+    /// Bit in ProtocolConfig::allowed_voting_modes
+    pub fn bit(&self) -> u8 {
+        match self {
+            VotingMode::Linear => 1,
+            VotingMode::Quadratic => 2,
+            VotingMode::Conviction => 4,
+        }
+    }
+}
+
+/// Weight multiplier for a conviction vote held for the entire voting period
+pub const CONVICTION_MAX_MULTIPLIER_BPS: u64 = 30000; // 3x
+
+fn integer_sqrt(value: u64) -> u64 {
+    if value < 2 {
+        return value;
+    }
+    // Newton's method from an over-estimate
+    let mut x = value;
+    let mut y = x / 2 + x % 2;
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+/// Proposal lifecycle
+///   Active -> Succeeded | Defeated (finalize_proposal) | Cancelled (cancel_proposal)
+///   Succeeded -> Queued (queue_proposal) | Expired (finalize_proposal after the grace period)
+///   Queued -> Executed | Cancelled (guardian veto) | Expired (finalize_proposal after the grace period)
+/// Defeated, Executed, Cancelled and Expired are final and can be closed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalStatus {
+    Active,
+    Succeeded,
+    Defeated,
+    Queued,
+    Executed,
+    Cancelled,
+    Expired,
+}
+
+impl ProposalStatus {
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            ProposalStatus::Defeated | ProposalStatus::Executed | ProposalStatus::Cancelled | ProposalStatus::Expired
+        )
+    }
+}
+
+/// How long a succeeded or queued proposal stays executable before it expires
+pub const EXECUTION_GRACE_PERIOD: i64 = 86400 * 14;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
 pub enum ProposalType {
     UpdateMinReputationForVouching,
     UpdateDecayRate,
     UpdateVouchLockupPeriod,
     UpdateSlashThreshold,
     UpdateMaxTrustMultiplier,
+    UpdateTaskFraudSlashRate,
+    UpdateCollusionSlashRate,
+    UpdateMaliciousSlashRate,
+    UpdateVouchFee,
+    UpdateProposalFee,
+    TreasurySpend, // Executed through spend_treasury
+    ExecuteInstruction, // Invokes the attached ProposalInstruction as the governance PDA
+    UpdateVotingPeriod,
+    UpdateQuorumVotes,
+    UpdateQuorumBps,
+    UpdateMajorityBps,
+    UpdateMinProposerReputation,
+    UpdateMinVoterReputation,
+    UpdateTimelockDelay,
+    UpdateAllowedVotingModes,
+    Batch, // Applies every change in the attached ProposalBatch, or none
+    UpdateProposalDeposit,
+}
+
+pub const MAX_BATCH_CHANGES: usize = 8;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct ParameterChange {
+    pub proposal_type: ProposalType,
+    pub new_value: u64,
+}
+
+/// Parameter changes carried by a Batch proposal
+/// Written once by the proposer and immutable afterwards, like ProposalInstruction
+#[account]
+pub struct ProposalBatch {
+    pub proposal: Pubkey,
+    pub changes: Vec<ParameterChange>, // At most MAX_BATCH_CHANGES
+    pub bump: u8,
+}
+
+impl ProposalBatch {
+    pub const LEN: usize = 8 + 32 + 4 + (MAX_BATCH_CHANGES * (1 + 8)) + 1;
+}
+
+/// Seed of the DAO-owned PDA that signs instructions from passed proposals
+/// Hand admin authorities to it with transfer_admin_to_governance
+pub const GOVERNANCE_AUTHORITY_SEED: &[u8] = b"governance";
+
+pub const MAX_PROPOSAL_IX_ACCOUNTS: usize = 10;
+pub const MAX_PROPOSAL_IX_DATA: usize = 512;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProposalAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// Serialized instruction carried by an ExecuteInstruction proposal
+/// Written once by the proposer and immutable afterwards, so voters see exactly what runs
+#[account]
+pub struct ProposalInstruction {
+    pub proposal: Pubkey,
+    pub program_id: Pubkey,
+    pub accounts: [ProposalAccountMeta; MAX_PROPOSAL_IX_ACCOUNTS],
+    pub account_count: u8,
+    pub data: [u8; MAX_PROPOSAL_IX_DATA],
+    pub data_len: u16,
+    pub bump: u8,
+}
+
+impl ProposalInstruction {
+    pub const LEN: usize = 8 + 32 + 32 + (MAX_PROPOSAL_IX_ACCOUNTS * 34) + 1 + MAX_PROPOSAL_IX_DATA + 2 + 1;
+    
+    pub fn to_instruction(&self) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: self.accounts[..self.account_count as usize]
+                .iter()
+                .map(|meta| AccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: self.data[..self.data_len as usize].to_vec(),
+        }
+    }
 }
 
 #[derive(Accounts)]
@@ -37,68 +296,193 @@ pub struct CreateProposal<'info> {
     pub proposer: Signer<'info>,
     
     #[account(
-        seeds = [b"agent", proposer.key().as_ref()],
+        mut,
+        seeds = [b"agent", proposer_profile.agent_id.as_ref()],
         bump = proposer_profile.bump,
+        constraint = is_authorized_signer(&proposer_profile, &proposer.key(), session.as_deref(), SCOPE_PROPOSE)?
     )]
     pub proposer_profile: Account<'info, crate::state::AgentProfile>,
     
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
     #[account(
         init,
         payer = proposer,
         space = GovernanceProposal::LEN,
-        seeds = [
-            b"proposal", 
-            proposer.key().as_ref(),
-            &Clock::get()?.unix_timestamp.to_le_bytes()
-        ],
+        seeds = [b"proposal", &config.proposal_count.to_le_bytes()],
         bump
     )]
     pub proposal: Account<'info, GovernanceProposal>,
     
+    /// The proposer's auto-vote, so it can't vote again directly or by delegation
     #[account(
-        seeds = [b"config"],
-        bump = config.bump,
+        init,
+        payer = proposer,
+        space = VoteRecord::LEN,
+        seeds = [b"vote", proposal.key().as_ref(), proposer_profile.agent_id.as_ref()],
+        bump
     )]
-    pub config: Account<'info, ProtocolConfig>,
+    pub proposer_vote_record: Account<'info, VoteRecord>,
+    
+    #[account(
+        mut,
+        associated_token::mint = config.reputation_mint,
+        associated_token::authority = proposer,
+    )]
+    pub proposer_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(
+        mut,
+        address = treasury.token_account
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        associated_token::mint = config.reputation_mint,
+        associated_token::authority = deposit_escrow,
+    )]
+    pub deposit_escrow_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: PDA that owns the deposit escrow account
+    #[account(
+        seeds = [b"escrow", proposal.key().as_ref()],
+        bump,
+    )]
+    pub deposit_escrow: UncheckedAccount<'info>,
     
+    pub token_program: Program<'info, token::Token>,
     pub system_program: Program<'info, System>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
 }
 
 pub fn create_proposal(
     ctx: Context<CreateProposal>,
     proposal_type: ProposalType,
     new_value: u64,
+    recipient: Pubkey,
+    voting_mode: VotingMode,
     description: [u8; 200],
     desc_len: u8,
 ) -> Result<()> {
     require!(desc_len <= 200, ReputationError::DescriptionTooLong);
+    require!(
+        ctx.accounts.config.allowed_voting_modes & voting_mode.bit() != 0,
+        ReputationError::VotingModeNotAllowed
+    );
     
     let proposer_profile = &ctx.accounts.proposer_profile;
     
     // Require minimum reputation to propose (prevents spam)
     require!(
-        proposer_profile.reputation_score >= 1000,
+        proposer_profile.reputation_score >= ctx.accounts.config.min_proposer_reputation,
         ReputationError::InsufficientReputation
     );
     
+    // Proposal fee goes to the treasury
+    deposit_to_treasury(
+        &mut ctx.accounts.treasury,
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.proposer_token_account.to_account_info(),
+                to: ctx.accounts.treasury_token_account.to_account_info(),
+                authority: ctx.accounts.proposer.to_account_info(),
+            },
+        ),
+        ctx.accounts.config.proposal_fee,
+    )?;
+    
+    // Refundable deposit, settled once the proposal is final
+    let deposit = ctx.accounts.config.proposal_deposit;
+    if deposit > 0 {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.proposer_token_account.to_account_info(),
+                    to: ctx.accounts.deposit_escrow_token_account.to_account_info(),
+                    authority: ctx.accounts.proposer.to_account_info(),
+                },
+            ),
+            deposit,
+        )?;
+    }
+    
     let clock = Clock::get()?;
+    let config = &mut ctx.accounts.config;
     let proposal = &mut ctx.accounts.proposal;
+    let proposer_profile = &mut ctx.accounts.proposer_profile;
+    proposer_profile.open_proposals = proposer_profile.open_proposals.saturating_add(1);
     
-    proposal.proposer = ctx.accounts.proposer.key();
+    proposal.id = config.proposal_count;
+    config.proposal_count = config.proposal_count.saturating_add(1);
+    proposal.proposer = proposer_profile.agent_id;
     proposal.proposal_type = proposal_type;
     proposal.new_value = new_value;
+    proposal.recipient = recipient;
+    proposal.deposit = deposit;
+    proposal.deposit_settled = false;
+    proposal.deposit_from = ctx.accounts.proposer_token_account.key();
     proposal.description = description;
     proposal.votes_for = 0;
     proposal.votes_against = 0;
-    proposal.voting_ends_at = clock.unix_timestamp + 86400 * 3; // 3 day voting period
-    proposal.executed = false;
+    proposal.votes_abstain = 0;
+    proposal.vote_seconds = [0; 3];
+    proposal.participation = 0;
+    proposal.voting_mode = voting_mode;
+    proposal.voting_ends_at = clock.unix_timestamp + config.voting_period;
+    proposal.snapshot_slot = clock.slot;
+    proposal.snapshot_timestamp = clock.unix_timestamp;
+    config.take_snapshot(proposal.snapshot_slot);
+    proposal.quorum = config.quorum();
+    proposal.majority_bps = config.majority_bps;
+    proposal.queued_at = 0;
+    proposal.eta = 0;
+    proposal.vetoed_by = Pubkey::default();
+    proposal.vetoed_at = 0;
+    proposal.executed_at = 0;
+    proposal.finalized_at = 0;
+    proposal.cancelled_at = 0;
+    proposal.status = ProposalStatus::Active;
     proposal.bump = ctx.bumps.proposal;
     
-    // Auto-vote with proposer's reputation
-    proposal.votes_for = proposer_profile.reputation_score;
+    // Auto-vote with proposer's reputation at the snapshot
+    let reputation = proposer_profile
+        .reputation_at(proposal.snapshot_slot)
+        .ok_or(ReputationError::NoReputationAtSnapshot)?;
+    let vote_weight = proposal.vote_weight(reputation);
+    proposal.add_votes(VoteChoice::For, vote_weight, clock.unix_timestamp);
+    proposal.participation = reputation;
+    
+    let vote_record = &mut ctx.accounts.proposer_vote_record;
+    vote_record.voter = proposal.proposer;
+    vote_record.proposal = proposal.key();
+    vote_record.cast_by = proposal.proposer;
+    vote_record.payer = ctx.accounts.proposer.key();
+    vote_record.reputation = reputation;
+    vote_record.vote_weight = vote_weight;
+    vote_record.delegated_weight = 0;
+    vote_record.choice = VoteChoice::For;
+    vote_record.voted_at = clock.unix_timestamp;
+    vote_record.bump = ctx.bumps.proposer_vote_record;
     
-    msg!("Governance proposal created by {}: {:?} = {}", 
-        proposal.proposer, proposal.proposal_type, proposal.new_value);
+    msg!("Governance proposal #{} created by {}: {:?} = {}", 
+        proposal.id, proposal.proposer, proposal.proposal_type, proposal.new_value);
     
     Ok(())
 }
@@ -109,41 +493,70 @@ pub struct VoteOnProposal<'info> {
     pub voter: Signer<'info>,
     
     #[account(
-        seeds = [b"agent", voter.key().as_ref()],
+        seeds = [b"agent", voter_profile.agent_id.as_ref()],
         bump = voter_profile.bump,
+        constraint = is_authorized_signer(&voter_profile, &voter.key(), session.as_deref(), SCOPE_VOTE)?
     )]
     pub voter_profile: Account<'info, crate::state::AgentProfile>,
     
     #[account(mut)]
     pub proposal: Account<'info, GovernanceProposal>,
     
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    /// Required for ExecuteInstruction proposals so nobody votes on an empty payload
+    #[account(
+        seeds = [b"proposal_ix", proposal.key().as_ref()],
+        bump = proposal_instruction.bump,
+    )]
+    pub proposal_instruction: Option<Account<'info, ProposalInstruction>>,
+    
+    /// Required for Batch proposals
+    #[account(
+        seeds = [b"proposal_batch", proposal.key().as_ref()],
+        bump = proposal_batch.bump,
+    )]
+    pub proposal_batch: Option<Account<'info, ProposalBatch>>,
+    
     #[account(
         init,
         payer = voter,
         space = VoteRecord::LEN,
-        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        seeds = [b"vote", proposal.key().as_ref(), voter_profile.agent_id.as_ref()],
         bump
     )]
     pub vote_record: Account<'info, VoteRecord>,
     
     pub system_program: Program<'info, System>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
+    // remaining_accounts: (delegation, delegator_profile, delegator_vote_record) per delegator
 }
 
 #[account]
 pub struct VoteRecord {
-    pub voter: Pubkey,
+    pub voter: Pubkey, // Whose weight this record counts
     pub proposal: Pubkey,
-    pub vote_weight: u64, // Reputation at time of voting
-    pub is_for: bool,
-    pub voted_at: i64,
+    pub cast_by: Pubkey, // The voter itself, or its delegate
+    pub payer: Pubkey, // Paid the record's rent and gets it back on close
+    pub reputation: u64, // Reputation at the proposal snapshot
+    pub vote_weight: u64, // Weight counted under the proposal's voting mode
+    pub delegated_weight: u64, // Delegators' weight cast alongside a direct vote
+    pub choice: VoteChoice,
+    pub voted_at: i64, // Reset by change_vote
     pub bump: u8,
 }
 
 impl VoteRecord {
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 1 + 8 + 1;
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 1 + 8 + 1;
 }
 
-pub fn vote_proposal(ctx: Context<VoteOnProposal>, is_for: bool) -> Result<()> {
+pub fn vote_proposal<'info>(ctx: Context<'_, '_, '_, 'info, VoteOnProposal<'info>>, choice: VoteChoice) -> Result<()> {
     let voter_profile = &ctx.accounts.voter_profile;
     let proposal = &mut ctx.accounts.proposal;
     let clock = Clock::get()?;
@@ -153,165 +566,1321 @@ pub fn vote_proposal(ctx: Context<VoteOnProposal>, is_for: bool) -> Result<()> {
         clock.unix_timestamp < proposal.voting_ends_at,
         ReputationError::VotingPeriodEnded
     );
-    require!(!proposal.executed, ReputationError::ProposalAlreadyExecuted);
+    require!(proposal.status == ProposalStatus::Active, ReputationError::InvalidProposalStatus);
+    
+    // Weight is fixed at the snapshot so reputation gained mid-vote doesn't count
+    let reputation = voter_profile
+        .reputation_at(proposal.snapshot_slot)
+        .ok_or(ReputationError::NoReputationAtSnapshot)?;
     require!(
-        voter_profile.reputation_score >= 100,
+        reputation >= ctx.accounts.config.min_voter_reputation,
         ReputationError::InsufficientReputation
     );
+    let vote_weight = proposal.vote_weight(reputation);
+    require!(
+        proposal.proposal_type != ProposalType::ExecuteInstruction
+            || ctx.accounts.proposal_instruction.is_some(),
+        ReputationError::ProposalInstructionMissing
+    );
+    require!(
+        proposal.proposal_type != ProposalType::Batch || ctx.accounts.proposal_batch.is_some(),
+        ReputationError::ProposalBatchMissing
+    );
+    
+    let voter = voter_profile.agent_id;
+    let proposal_key = proposal.key();
     
     // Record vote
     let vote_record = &mut ctx.accounts.vote_record;
-    vote_record.voter = ctx.accounts.voter.key();
-    vote_record.proposal = proposal.key();
-    vote_record.vote_weight = voter_profile.reputation_score;
-    vote_record.is_for = is_for;
+    vote_record.voter = voter;
+    vote_record.proposal = proposal_key;
+    vote_record.cast_by = voter;
+    vote_record.payer = ctx.accounts.voter.key();
+    vote_record.reputation = reputation;
+    vote_record.vote_weight = vote_weight;
+    vote_record.delegated_weight = 0;
+    vote_record.choice = choice;
     vote_record.voted_at = clock.unix_timestamp;
     vote_record.bump = ctx.bumps.vote_record;
     
-    // Apply reputation-weighted vote
-    if is_for {
-        proposal.votes_for = proposal.votes_for.saturating_add(voter_profile.reputation_score);
-    } else {
-        proposal.votes_against = proposal.votes_against.saturating_add(voter_profile.reputation_score);
-    }
+    // Apply mode-weighted vote; raw reputation counts toward quorum
+    proposal.participation = proposal.participation.saturating_add(reputation);
+    proposal.add_votes(choice, vote_weight, clock.unix_timestamp);
     
-    msg!("Vote cast: {} voted {} with weight {}", 
-        vote_record.voter, if is_for { "FOR" } else { "AGAINST" }, vote_record.vote_weight);
+    // A first batch of delegated weight can ride along; cast_delegated_votes takes the rest
+    cast_delegated(
+        ctx.program_id,
+        proposal,
+        vote_record,
+        &ctx.accounts.voter.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        ctx.remaining_accounts,
+        ctx.accounts.config.min_voter_reputation,
+    )?;
+    
+    msg!("Vote cast: {} voted {} with weight {} (+{} delegated)", 
+        vote_record.voter, choice.label(), vote_record.vote_weight, vote_record.delegated_weight);
     
     Ok(())
 }
 
 #[derive(Accounts)]
-pub struct ExecuteProposal<'info> {
+pub struct CastDelegatedVotes<'info> {
     #[account(mut)]
-    pub executor: Signer<'info>,
+    pub delegate: Signer<'info>,
+    
+    #[account(
+        seeds = [b"agent", delegate_profile.agent_id.as_ref()],
+        bump = delegate_profile.bump,
+        constraint = is_authorized_signer(&delegate_profile, &delegate.key(), session.as_deref(), SCOPE_VOTE)?
+    )]
+    pub delegate_profile: Account<'info, crate::state::AgentProfile>,
     
     #[account(mut)]
     pub proposal: Account<'info, GovernanceProposal>,
     
     #[account(
-        mut,
         seeds = [b"config"],
         bump = config.bump,
     )]
     pub config: Account<'info, ProtocolConfig>,
+    
+    /// The delegate's own vote, whose choice the delegated weight follows
+    #[account(
+        mut,
+        seeds = [b"vote", proposal.key().as_ref(), delegate_profile.agent_id.as_ref()],
+        bump = vote_record.bump,
+        constraint = vote_record.cast_by == delegate_profile.agent_id
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
+    // remaining_accounts: (delegation, delegator_profile, delegator_vote_record) per delegator
 }
 
-pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+/// Cast another batch of delegated weight after the delegate's own vote, so the
+/// number of delegators isn't bounded by one transaction's account limit
+pub fn cast_delegated_votes<'info>(ctx: Context<'_, '_, '_, 'info, CastDelegatedVotes<'info>>) -> Result<()> {
     let proposal = &mut ctx.accounts.proposal;
-    let config = &mut ctx.accounts.config;
-    let clock = Clock::get()?;
-    
-    // Validation checks
-    require!(
-        clock.unix_timestamp >= proposal.voting_ends_at,
-        ReputationError::VotingPeriodActive
-    );
-    require!(!proposal.executed, ReputationError::ProposalAlreadyExecuted);
+    let vote_record = &mut ctx.accounts.vote_record;
+    let now = Clock::get()?.unix_timestamp;
     
-    // Check quorum: need at least 10000 total reputation voted
-    let total_votes = proposal.votes_for.saturating_add(proposal.votes_against);
-    require!(total_votes >= 10000, ReputationError::QuorumNotReached);
+    require!(proposal.status == ProposalStatus::Active, ReputationError::InvalidProposalStatus);
+    require!(now < proposal.voting_ends_at, ReputationError::VotingPeriodEnded);
+    require!(!ctx.remaining_accounts.is_empty(), ReputationError::InvalidDelegation);
     
-    // Check majority: 51% to pass
-    require!(
-        proposal.votes_for > proposal.votes_against,
-        ReputationError::ProposalRejected
-    );
+    let before = vote_record.delegated_weight;
+    cast_delegated(
+        ctx.program_id,
+        proposal,
+        vote_record,
+        &ctx.accounts.delegate.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        ctx.remaining_accounts,
+        ctx.accounts.config.min_voter_reputation,
+    )?;
     
-    // Execute the parameter change
-    match proposal.proposal_type {
-        ProposalType::UpdateMinReputationForVouching => {
-            config.min_reputation_for_vouching = proposal.new_value;
-            msg!("Updated min_reputation_for_vouching to {}", proposal.new_value);
-        }
-        ProposalType::UpdateDecayRate => {
-            require!(proposal.new_value <= 1000, ReputationError::InvalidParameter); // Max 10% daily
-            config.decay_rate_per_day = proposal.new_value;
-            msg!("Updated decay_rate_per_day to {}", proposal.new_value);
-        }
-        ProposalType::UpdateVouchLockupPeriod => {
-            config.vouch_lockup_period = proposal.new_value as i64;
-            msg!("Updated vouch_lockup_period to {}", proposal.new_value);
+    msg!("Delegated votes cast by {}: +{} on {}",
+        vote_record.voter, vote_record.delegated_weight.saturating_sub(before), vote_record.choice.label());
+    Ok(())
+}
+
+/// Cast delegators' weight with the delegate's choice; each delegator gets its own
+/// VoteRecord so it is counted once. remaining_accounts hold
+/// (delegation, delegator_profile, delegator_vote_record) per delegator
+fn cast_delegated<'info>(
+    program_id: &Pubkey,
+    proposal: &mut GovernanceProposal,
+    delegate_record: &mut VoteRecord,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    min_voter_reputation: u64,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let delegate = delegate_record.voter;
+    let proposal_key = delegate_record.proposal;
+    let choice = delegate_record.choice;
+    let mut delegated_weight: u64 = 0;
+    let mut delegated_reputation: u64 = 0;
+    require!(remaining_accounts.len() % 3 == 0, ReputationError::InvalidDelegation);
+    for accounts in remaining_accounts.chunks(3) {
+        let delegation = load_program_account::<Delegation>(&accounts[0], program_id)?;
+        let delegator_profile = load_program_account::<AgentProfile>(&accounts[1], program_id)?;
+        let delegator_record = &accounts[2];
+        require!(
+            delegation.delegate == delegate && delegator_profile.agent_id == delegation.delegator,
+            ReputationError::InvalidDelegation
+        );
+        
+        // Delegations made after the snapshot can't move weight into a live vote
+        if delegation.delegated_slot >= proposal.snapshot_slot {
+            msg!("Skipping {}: delegated after snapshot", delegation.delegator);
+            continue;
         }
-        ProposalType::UpdateSlashThreshold => {
-            config.slash_threshold = proposal.new_value;
-            msg!("Updated slash_threshold to {}", proposal.new_value);
+        let Some(delegator_reputation) = delegator_profile.reputation_at(proposal.snapshot_slot) else {
+            continue;
+        };
+        // Delegating doesn't let agents below the voting threshold vote
+        if delegator_reputation < min_voter_reputation {
+            msg!("Skipping {}: below minimum voter reputation", delegation.delegator);
+            continue;
         }
-        ProposalType::UpdateMaxTrustMultiplier => {
-            require!(proposal.new_value >= 10000 && proposal.new_value <= 50000, ReputationError::InvalidParameter);
-            config.max_trust_multiplier = proposal.new_value;
-            msg!("Updated max_trust_multiplier to {}", proposal.new_value);
+        // Mode applies per delegator, so pooling doesn't dodge quadratic weighting
+        let weight = proposal.vote_weight(delegator_reputation);
+        
+        let (record_key, record_bump) = Pubkey::find_program_address(
+            &[b"vote", proposal_key.as_ref(), delegation.delegator.as_ref()],
+            program_id,
+        );
+        require!(delegator_record.key() == record_key, ReputationError::InvalidDelegation);
+        if !delegator_record.data_is_empty() {
+            msg!("Skipping {}: already voted", delegation.delegator);
+            continue;
         }
+        
+        let seeds = &[b"vote", proposal_key.as_ref(), delegation.delegator.as_ref(), &[record_bump]];
+        create_pda_account(
+            payer,
+            delegator_record,
+            system_program,
+            VoteRecord::LEN,
+            program_id,
+            &seeds[..],
+        )?;
+        let record = VoteRecord {
+            voter: delegation.delegator,
+            proposal: proposal_key,
+            cast_by: delegate,
+            payer: payer.key(),
+            reputation: delegator_reputation,
+            vote_weight: weight,
+            delegated_weight: 0,
+            choice,
+            voted_at: now,
+            bump: record_bump,
+        };
+        record.try_serialize(&mut &mut delegator_record.try_borrow_mut_data()?[..])?;
+        
+        delegated_weight = delegated_weight.saturating_add(weight);
+        delegated_reputation = delegated_reputation.saturating_add(delegator_reputation);
     }
     
-    proposal.executed = true;
-    
-    msg!("Proposal executed successfully by {}", ctx.accounts.executor.key());
-    
+    delegate_record.delegated_weight = delegate_record.delegated_weight.saturating_add(delegated_weight);
+    proposal.participation = proposal.participation.saturating_add(delegated_reputation);
+    proposal.add_votes(choice, delegated_weight, now);
     Ok(())
 }
 
 #[derive(Accounts)]
-pub struct SlashAgent<'info> {
-    #[account(mut)]
-    pub slasher: Signer<'info>,
+pub struct ChangeVote<'info> {
+    pub voter: Signer<'info>,
     
     #[account(
-        seeds = [b"agent", slasher.key().as_ref()],
-        bump = slasher_profile.bump,
+        seeds = [b"agent", voter_profile.agent_id.as_ref()],
+        bump = voter_profile.bump,
+        constraint = is_authorized_signer(&voter_profile, &voter.key(), session.as_deref(), SCOPE_VOTE)?
     )]
-    pub slasher_profile: Account<'info, crate::state::AgentProfile>,
+    pub voter_profile: Account<'info, crate::state::AgentProfile>,
+    
+    #[account(mut)]
+    pub proposal: Account<'info, GovernanceProposal>,
     
     #[account(
         mut,
-        seeds = [b"agent", target.key().as_ref()],
-        bump = target_profile.bump,
+        seeds = [b"vote", proposal.key().as_ref(), voter_profile.agent_id.as_ref()],
+        bump = vote_record.bump,
+        constraint = vote_record.cast_by == voter_profile.agent_id
     )]
-    pub target_profile: Account<'info, crate::state::AgentProfile>,
+    pub vote_record: Account<'info, VoteRecord>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
+    // remaining_accounts: delegator VoteRecords this voter cast (writable), any batch of them
+}
+
+/// Move a cast vote to another choice, along with whichever delegated votes it cast
+/// are passed; calling again with the same choice moves further batches of them
+/// Conviction accrual restarts from the time of the change
+pub fn change_vote<'info>(ctx: Context<'_, '_, '_, 'info, ChangeVote<'info>>, choice: VoteChoice) -> Result<()> {
+    let proposal = &mut ctx.accounts.proposal;
+    let vote_record = &mut ctx.accounts.vote_record;
+    let now = Clock::get()?.unix_timestamp;
+    
+    require!(proposal.status == ProposalStatus::Active, ReputationError::InvalidProposalStatus);
+    require!(now < proposal.voting_ends_at, ReputationError::VotingPeriodEnded);
+    
+    let voter = ctx.accounts.voter_profile.agent_id;
+    let proposal_key = proposal.key();
+    let old_choice = vote_record.choice;
+    let mut moved = false;
+    
+    if old_choice != choice {
+        let new_weight = proposal.vote_weight(vote_record.reputation);
+        proposal.remove_votes(old_choice, vote_record.vote_weight, vote_record.voted_at);
+        proposal.add_votes(choice, new_weight, now);
+        vote_record.choice = choice;
+        vote_record.vote_weight = new_weight;
+        vote_record.voted_at = now;
+        moved = true;
+    }
+    
+    let mut seen: Vec<Pubkey> = Vec::with_capacity(ctx.remaining_accounts.len());
+    let mut old_delegated: u64 = 0;
+    let mut new_delegated: u64 = 0;
+    for info in ctx.remaining_accounts.iter() {
+        require!(info.is_writable && !seen.contains(info.key), ReputationError::InvalidDelegation);
+        seen.push(info.key());
+        
+        let mut record = load_program_account::<VoteRecord>(info, ctx.program_id)?;
+        require!(
+            record.proposal == proposal_key && record.cast_by == voter && record.voter != voter,
+            ReputationError::InvalidDelegation
+        );
+        if record.choice == choice {
+            continue;
+        }
+        
+        let weight = proposal.vote_weight(record.reputation);
+        proposal.remove_votes(record.choice, record.vote_weight, record.voted_at);
+        proposal.add_votes(choice, weight, now);
+        old_delegated = old_delegated.saturating_add(record.vote_weight);
+        new_delegated = new_delegated.saturating_add(weight);
+        
+        record.choice = choice;
+        record.vote_weight = weight;
+        record.voted_at = now;
+        record.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        moved = true;
+    }
+    require!(moved, ReputationError::InvalidParameter);
+    
+    vote_record.delegated_weight = vote_record.delegated_weight
+        .saturating_sub(old_delegated)
+        .saturating_add(new_delegated);
+    
+    msg!("Vote changed: {} {} -> {} with weight {} ({} -> {} delegated moved)",
+        voter, old_choice.label(), choice.label(), vote_record.vote_weight, old_delegated, new_delegated);
+    Ok(())
+}
+
+/// Create a PDA owned by this program, topping up instead if someone pre-funded the address
+fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    program_id: &Pubkey,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let signer = &[seeds];
+    
+    if account.lamports() == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                CreateAccount { from: payer.clone(), to: account.clone() },
+                signer,
+            ),
+            rent,
+            space as u64,
+            program_id,
+        );
+    }
+    
+    let top_up = rent.saturating_sub(account.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer { from: payer.clone(), to: account.clone() },
+            ),
+            top_up,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Allocate { account_to_allocate: account.clone() },
+            signer,
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Assign { account_to_assign: account.clone() },
+            signer,
+        ),
+        program_id,
+    )
+}
+
+/// Deserialize a program-owned account passed through remaining_accounts
+fn load_program_account<T: AccountDeserialize>(info: &AccountInfo, program_id: &Pubkey) -> Result<T> {
+    require!(info.owner == program_id, ReputationError::InvalidDelegation);
+    let data = info.try_borrow_data()?;
+    T::try_deserialize(&mut &data[..])
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
     
-    /// CHECK: The agent being slashed
-    pub target: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = proposal.proposal_type != ProposalType::TreasurySpend
+    )]
+    pub proposal: Account<'info, GovernanceProposal>,
     
     #[account(
+        mut,
         seeds = [b"config"],
         bump = config.bump,
     )]
     pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        seeds = [b"proposal_ix", proposal.key().as_ref()],
+        bump = proposal_instruction.bump,
+    )]
+    pub proposal_instruction: Option<Account<'info, ProposalInstruction>>,
+    
+    #[account(
+        seeds = [b"proposal_batch", proposal.key().as_ref()],
+        bump = proposal_batch.bump,
+    )]
+    pub proposal_batch: Option<Account<'info, ProposalBatch>>,
+    
+    /// CHECK: DAO-owned PDA, signs the stored instruction
+    #[account(
+        seeds = [GOVERNANCE_AUTHORITY_SEED],
+        bump,
+    )]
+    pub governance_authority: UncheckedAccount<'info>,
+    // remaining_accounts: every account referenced by the stored instruction, plus its program
 }
 
-/// Dynamic slashing based on configurable thresholds
-pub fn slash_agent(ctx: Context<SlashAgent>, evidence_hash: [u8; 32]) -> Result<()> {
-    let slasher_profile = &ctx.accounts.slasher_profile;
-    let target_profile = &mut ctx.accounts.target_profile;
-    
-    // Require slasher to have significant reputation
+/// Queued, timelock elapsed and still inside the execution grace period
+pub fn require_proposal_executable(proposal: &GovernanceProposal, clock: &Clock) -> Result<()> {
+    require!(proposal.status != ProposalStatus::Executed, ReputationError::ProposalAlreadyExecuted);
+    require!(proposal.status == ProposalStatus::Queued, ReputationError::ProposalNotQueued);
+    require!(clock.unix_timestamp >= proposal.eta, ReputationError::TimelockActive);
     require!(
-        slasher_profile.reputation_score >= 5000,
-        ReputationError::InsufficientReputation
+        clock.unix_timestamp <= proposal.eta.saturating_add(EXECUTION_GRACE_PERIOD),
+        ReputationError::ProposalExpired
     );
+    Ok(())
+}
+
+pub fn execute_proposal<'info>(ctx: Context<'_, '_, '_, 'info, ExecuteProposal<'info>>) -> Result<()> {
+    let proposal = &mut ctx.accounts.proposal;
+    let config = &mut ctx.accounts.config;
+    let clock = Clock::get()?;
     
-    // Calculate slash amount (dynamic based on target's reputation)
-    // Higher reputation agents lose more when slashed
-    let slash_percentage: u64 = 2000; // 20% base slash (configurable via governance)
-    let slash_amount = target_profile.reputation_score
-        .saturating_mul(slash_percentage)
-        .saturating_div(10000);
-    
-    // Apply slash
-    target_profile.reputation_score = target_profile.reputation_score.saturating_sub(slash_amount);
+    // Validation checks
+    require_proposal_executable(proposal, &clock)?;
     
-    // Deactivate if reputation too low
-    if target_profile.reputation_score < 100 {
-        target_profile.is_active = false;
+    match proposal.proposal_type {
+        ProposalType::TreasurySpend => {
+            // Excluded by the account constraint; paid out by spend_treasury
+            return err!(ReputationError::InvalidParameter);
+        }
+        ProposalType::ExecuteInstruction => {
+            let Some(proposal_ix) = ctx.accounts.proposal_instruction.as_ref() else {
+                return err!(ReputationError::ProposalInstructionMissing);
+            };
+            
+            // Persist the Executed status before the CPI so the instruction can't re-enter
+            // execute_proposal on this same proposal
+            proposal.status = ProposalStatus::Executed;
+            proposal.executed_at = clock.unix_timestamp;
+            proposal.exit(ctx.program_id)?;
+            
+            let seeds = &[GOVERNANCE_AUTHORITY_SEED, &[ctx.bumps.governance_authority]];
+            invoke_signed(&proposal_ix.to_instruction(), ctx.remaining_accounts, &[&seeds[..]])?;
+            
+            // The instruction may have changed the config (e.g. handing authority back);
+            // reload so our exit doesn't overwrite it with the stale copy
+            config.reload()?;
+            msg!("Executed proposal instruction on program {}", proposal_ix.program_id);
+        }
+        ProposalType::Batch => {
+            let Some(batch) = ctx.accounts.proposal_batch.as_ref() else {
+                return err!(ReputationError::ProposalBatchMissing);
+            };
+            
+            // Apply every change to a copy first, so one out-of-bounds value leaves
+            // the config untouched and later changes see earlier ones (e.g. min voter
+            // reputation checked against a new min proposer reputation)
+            let mut staged = ProtocolConfig::clone(config);
+            for change in batch.changes.iter() {
+                apply_parameter_change(&mut staged, &change.proposal_type, change.new_value)?;
+            }
+            config.set_inner(staged);
+            msg!("Applied {} parameter changes", batch.changes.len());
+        }
+        ref proposal_type => apply_parameter_change(config, proposal_type, proposal.new_value)?,
     }
     
-    // Reward slasher with portion of slashed amount (5% bounty)
-    let bounty = slash_amount.saturating_mul(500).saturating_div(10000);
-    // In production: transfer bounty tokens to slasher
+    proposal.status = ProposalStatus::Executed;
+    proposal.executed_at = clock.unix_timestamp;
     
-    msg!("Agent {} slashed by {}: lost {} reputation (bounty: {})", 
-        target_profile.owner, slasher_profile.owner, slash_amount, bounty);
+    msg!("Proposal executed successfully by {}", ctx.accounts.executor.key());
     
     Ok(())
 }
+
+/// Apply a single parameter change to the config, checking its bounds
+/// TreasurySpend, ExecuteInstruction and Batch are not parameter changes
+fn apply_parameter_change(config: &mut ProtocolConfig, proposal_type: &ProposalType, value: u64) -> Result<()> {
+    match proposal_type {
+        ProposalType::UpdateMinReputationForVouching => {
+            require!(value <= ProtocolConfig::MAX_MIN_REPUTATION_FOR_VOUCHING, ReputationError::InvalidParameter);
+            config.min_reputation_for_vouching = value;
+            msg!("Updated min_reputation_for_vouching to {}", value);
+        }
+        ProposalType::UpdateDecayRate => {
+            require!(value <= ProtocolConfig::MAX_DECAY_RATE_BPS, ReputationError::InvalidParameter);
+            config.decay_rate_per_day = value;
+            msg!("Updated decay_rate_per_day to {}", value);
+        }
+        ProposalType::UpdateVouchLockupPeriod => {
+            config.vouch_lockup_period = seconds_in_range(value, 0, ProtocolConfig::MAX_VOUCH_LOCKUP_PERIOD)?;
+            msg!("Updated vouch_lockup_period to {}", value);
+        }
+        ProposalType::UpdateSlashThreshold => {
+            require_slash_rate_in_bounds(value)?;
+            config.slash_threshold = value;
+            msg!("Updated slash_threshold to {}", value);
+        }
+        ProposalType::UpdateMaxTrustMultiplier => {
+            require!(
+                (ProtocolConfig::MIN_TRUST_MULTIPLIER..=ProtocolConfig::MAX_TRUST_MULTIPLIER).contains(&value),
+                ReputationError::InvalidParameter
+            );
+            config.max_trust_multiplier = value;
+            msg!("Updated max_trust_multiplier to {}", value);
+        }
+        ProposalType::UpdateTaskFraudSlashRate => {
+            require_slash_rate_in_bounds(value)?;
+            config.task_fraud_slash_bps = value;
+            msg!("Updated task_fraud_slash_bps to {}", value);
+        }
+        ProposalType::UpdateCollusionSlashRate => {
+            require_slash_rate_in_bounds(value)?;
+            config.collusion_slash_bps = value;
+            msg!("Updated collusion_slash_bps to {}", value);
+        }
+        ProposalType::UpdateMaliciousSlashRate => {
+            require_slash_rate_in_bounds(value)?;
+            config.malicious_slash_bps = value;
+            msg!("Updated malicious_slash_bps to {}", value);
+        }
+        ProposalType::UpdateVouchFee => {
+            require!(value <= ProtocolConfig::MAX_VOUCH_FEE_BPS, ReputationError::InvalidParameter);
+            config.vouch_fee_bps = value;
+            msg!("Updated vouch_fee_bps to {}", value);
+        }
+        ProposalType::UpdateProposalFee => {
+            require!(value <= ProtocolConfig::MAX_PROPOSAL_FEE, ReputationError::InvalidParameter);
+            config.proposal_fee = value;
+            msg!("Updated proposal_fee to {}", value);
+        }
+        ProposalType::UpdateProposalDeposit => {
+            require!(value <= ProtocolConfig::MAX_PROPOSAL_DEPOSIT, ReputationError::InvalidParameter);
+            config.proposal_deposit = value;
+            msg!("Updated proposal_deposit to {}", value);
+        }
+        ProposalType::UpdateVotingPeriod => {
+            config.voting_period = seconds_in_range(
+                value,
+                ProtocolConfig::MIN_VOTING_PERIOD,
+                ProtocolConfig::MAX_VOTING_PERIOD,
+            )?;
+            msg!("Updated voting_period to {}", value);
+        }
+        ProposalType::UpdateQuorumVotes => {
+            require!(
+                (1..=ProtocolConfig::MAX_QUORUM_VOTES).contains(&value),
+                ReputationError::InvalidParameter
+            );
+            config.quorum_votes = value;
+            msg!("Updated quorum_votes to {}", value);
+        }
+        ProposalType::UpdateQuorumBps => {
+            require!(value <= ProtocolConfig::MAX_QUORUM_BPS, ReputationError::InvalidParameter);
+            config.quorum_bps = value;
+            msg!("Updated quorum_bps to {}", value);
+        }
+        ProposalType::UpdateMajorityBps => {
+            require!(
+                (ProtocolConfig::MIN_MAJORITY_BPS..=ProtocolConfig::MAX_MAJORITY_BPS).contains(&value),
+                ReputationError::InvalidParameter
+            );
+            config.majority_bps = value;
+            msg!("Updated majority_bps to {}", value);
+        }
+        ProposalType::UpdateMinProposerReputation => {
+            require!(
+                (1..=ProtocolConfig::MAX_MIN_PROPOSER_REPUTATION).contains(&value)
+                    && value >= config.min_voter_reputation,
+                ReputationError::InvalidParameter
+            );
+            config.min_proposer_reputation = value;
+            msg!("Updated min_proposer_reputation to {}", value);
+        }
+        ProposalType::UpdateMinVoterReputation => {
+            require!(
+                value <= config.min_proposer_reputation,
+                ReputationError::InvalidParameter
+            );
+            config.min_voter_reputation = value;
+            msg!("Updated min_voter_reputation to {}", value);
+        }
+        ProposalType::UpdateTimelockDelay => {
+            config.timelock_delay = seconds_in_range(
+                value,
+                ProtocolConfig::MIN_TIMELOCK_DELAY,
+                ProtocolConfig::MAX_TIMELOCK_DELAY,
+            )?;
+            msg!("Updated timelock_delay to {}", value);
+        }
+        ProposalType::UpdateAllowedVotingModes => {
+            require!(
+                value > 0 && value <= ProtocolConfig::ALL_VOTING_MODES as u64,
+                ReputationError::InvalidParameter
+            );
+            config.allowed_voting_modes = value as u8;
+            msg!("Updated allowed_voting_modes to {}", value);
+        }
+        ProposalType::TreasurySpend | ProposalType::ExecuteInstruction | ProposalType::Batch => {
+            return err!(ReputationError::InvalidParameter);
+        }
+    }
+    Ok(())
+}
+
+fn require_slash_rate_in_bounds(value: u64) -> Result<()> {
+    require!(
+        (ProtocolConfig::MIN_SLASH_BPS..=ProtocolConfig::MAX_SLASH_BPS).contains(&value),
+        ReputationError::InvalidParameter
+    );
+    Ok(())
+}
+
+/// A duration in seconds within [min, max], rejecting values that don't fit an i64
+fn seconds_in_range(value: u64, min: i64, max: i64) -> Result<i64> {
+    let seconds = i64::try_from(value).map_err(|_| ReputationError::InvalidParameter)?;
+    require!((min..=max).contains(&seconds), ReputationError::InvalidParameter);
+    Ok(seconds)
+}
+
+#[derive(Accounts)]
+pub struct AttachProposalInstruction<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    
+    #[account(
+        seeds = [b"agent", proposer_profile.agent_id.as_ref()],
+        bump = proposer_profile.bump,
+        constraint = is_authorized_signer(&proposer_profile, &proposer.key(), session.as_deref(), SCOPE_PROPOSE)?
+    )]
+    pub proposer_profile: Account<'info, crate::state::AgentProfile>,
+    
+    #[account(
+        constraint = proposal.proposer == proposer_profile.agent_id,
+        constraint = proposal.proposal_type == ProposalType::ExecuteInstruction
+    )]
+    pub proposal: Account<'info, GovernanceProposal>,
+    
+    #[account(
+        init,
+        payer = proposer,
+        space = ProposalInstruction::LEN,
+        seeds = [b"proposal_ix", proposal.key().as_ref()],
+        bump
+    )]
+    pub proposal_instruction: Account<'info, ProposalInstruction>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
+}
+
+/// Attach the instruction an ExecuteInstruction proposal will run
+/// Accounts flagged is_signer may only be the governance PDA
+pub fn attach_proposal_instruction(
+    ctx: Context<AttachProposalInstruction>,
+    program_id: Pubkey,
+    accounts: Vec<ProposalAccountMeta>,
+    data: Vec<u8>,
+) -> Result<()> {
+    require!(
+        accounts.len() <= MAX_PROPOSAL_IX_ACCOUNTS && data.len() <= MAX_PROPOSAL_IX_DATA,
+        ReputationError::InvalidProposalInstruction
+    );
+    require!(
+        Clock::get()?.unix_timestamp < ctx.accounts.proposal.voting_ends_at,
+        ReputationError::VotingPeriodEnded
+    );
+    
+    let (governance_authority, _) =
+        Pubkey::find_program_address(&[GOVERNANCE_AUTHORITY_SEED], ctx.program_id);
+    require!(
+        accounts.iter().all(|meta| !meta.is_signer || meta.pubkey == governance_authority),
+        ReputationError::InvalidProposalInstruction
+    );
+    
+    let proposal_ix = &mut ctx.accounts.proposal_instruction;
+    proposal_ix.proposal = ctx.accounts.proposal.key();
+    proposal_ix.program_id = program_id;
+    proposal_ix.accounts = [ProposalAccountMeta::default(); MAX_PROPOSAL_IX_ACCOUNTS];
+    proposal_ix.accounts[..accounts.len()].copy_from_slice(&accounts);
+    proposal_ix.account_count = accounts.len() as u8;
+    proposal_ix.data = [0u8; MAX_PROPOSAL_IX_DATA];
+    proposal_ix.data[..data.len()].copy_from_slice(&data);
+    proposal_ix.data_len = data.len() as u16;
+    proposal_ix.bump = ctx.bumps.proposal_instruction;
+    
+    msg!("Instruction for program {} attached to proposal {}", program_id, proposal_ix.proposal);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AttachProposalBatch<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    
+    #[account(
+        seeds = [b"agent", proposer_profile.agent_id.as_ref()],
+        bump = proposer_profile.bump,
+        constraint = is_authorized_signer(&proposer_profile, &proposer.key(), session.as_deref(), SCOPE_PROPOSE)?
+    )]
+    pub proposer_profile: Account<'info, crate::state::AgentProfile>,
+    
+    #[account(
+        constraint = proposal.proposer == proposer_profile.agent_id,
+        constraint = proposal.proposal_type == ProposalType::Batch
+    )]
+    pub proposal: Account<'info, GovernanceProposal>,
+    
+    #[account(
+        init,
+        payer = proposer,
+        space = ProposalBatch::LEN,
+        seeds = [b"proposal_batch", proposal.key().as_ref()],
+        bump
+    )]
+    pub proposal_batch: Account<'info, ProposalBatch>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
+}
+
+/// Attach the parameter changes a Batch proposal will apply
+/// Bounds are checked against the config at execution, but the shape is checked here
+pub fn attach_proposal_batch(ctx: Context<AttachProposalBatch>, changes: Vec<ParameterChange>) -> Result<()> {
+    require!(
+        !changes.is_empty() && changes.len() <= MAX_BATCH_CHANGES,
+        ReputationError::InvalidProposalBatch
+    );
+    require!(
+        Clock::get()?.unix_timestamp < ctx.accounts.proposal.voting_ends_at,
+        ReputationError::VotingPeriodEnded
+    );
+    
+    // Only parameter updates, each parameter at most once
+    for (i, change) in changes.iter().enumerate() {
+        require!(
+            !matches!(
+                change.proposal_type,
+                ProposalType::TreasurySpend | ProposalType::ExecuteInstruction | ProposalType::Batch
+            ),
+            ReputationError::InvalidProposalBatch
+        );
+        require!(
+            changes[..i].iter().all(|other| other.proposal_type != change.proposal_type),
+            ReputationError::InvalidProposalBatch
+        );
+    }
+    
+    let proposal_batch = &mut ctx.accounts.proposal_batch;
+    proposal_batch.proposal = ctx.accounts.proposal.key();
+    proposal_batch.changes = changes;
+    proposal_batch.bump = ctx.bumps.proposal_batch;
+    
+    msg!("{} parameter changes attached to proposal {}", 
+        proposal_batch.changes.len(), proposal_batch.proposal);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct TransferAdminToGovernance<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.authority == authority.key()
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"oracle_registry"],
+        bump = oracle_registry.bump,
+        constraint = oracle_registry.authority == authority.key()
+    )]
+    pub oracle_registry: Option<Account<'info, OracleRegistry>>,
+    
+    #[account(
+        mut,
+        seeds = [b"zk_registry".as_ref(), &[zk_registry.circuit as u8]],
+        bump = zk_registry.bump,
+        constraint = zk_registry.authority == authority.key()
+    )]
+    pub zk_registry: Option<Account<'info, ZKVerificationKey>>,
+    
+    /// CHECK: DAO-owned PDA that becomes the new authority
+    #[account(
+        seeds = [GOVERNANCE_AUTHORITY_SEED],
+        bump,
+    )]
+    pub governance_authority: UncheckedAccount<'info>,
+}
+
+/// One-way handover of the admin keys to the governance PDA
+/// Afterwards add_oracle, update_verification_key, register_task_issuer etc. only run
+/// through ExecuteInstruction proposals. Fund the PDA with SOL for instructions it pays rent for.
+pub fn transfer_admin_to_governance(ctx: Context<TransferAdminToGovernance>) -> Result<()> {
+    let governance_authority = ctx.accounts.governance_authority.key();
+    
+    ctx.accounts.config.authority = governance_authority;
+    if let Some(oracle_registry) = ctx.accounts.oracle_registry.as_mut() {
+        oracle_registry.authority = governance_authority;
+    }
+    if let Some(zk_registry) = ctx.accounts.zk_registry.as_mut() {
+        zk_registry.authority = governance_authority;
+    }
+    
+    msg!("Admin authority transferred to governance PDA {}", governance_authority);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct QueueProposal<'info> {
+    pub caller: Signer<'info>,
+    
+    #[account(mut)]
+    pub proposal: Account<'info, GovernanceProposal>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+/// Queue a succeeded proposal; it becomes executable after the timelock delay
+pub fn queue_proposal(ctx: Context<QueueProposal>) -> Result<()> {
+    let proposal = &mut ctx.accounts.proposal;
+    let clock = Clock::get()?;
+    
+    require!(proposal.status == ProposalStatus::Succeeded, ReputationError::InvalidProposalStatus);
+    require!(
+        clock.unix_timestamp <= proposal.voting_ends_at.saturating_add(EXECUTION_GRACE_PERIOD),
+        ReputationError::ProposalExpired
+    );
+    
+    proposal.status = ProposalStatus::Queued;
+    proposal.queued_at = clock.unix_timestamp;
+    proposal.eta = clock.unix_timestamp.saturating_add(ctx.accounts.config.timelock_delay);
+    
+    msg!("Proposal {} queued, executable at {}", proposal.key(), proposal.eta);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct VetoProposal<'info> {
+    pub guardian: Signer<'info>,
+    
+    #[account(mut)]
+    pub proposal: Account<'info, GovernanceProposal>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+/// Guardian veto of a queued proposal while its timelock is running
+pub fn veto_proposal(ctx: Context<VetoProposal>) -> Result<()> {
+    let proposal = &mut ctx.accounts.proposal;
+    let clock = Clock::get()?;
+    
+    require!(
+        ctx.accounts.config.is_guardian(&ctx.accounts.guardian.key()),
+        ReputationError::NotGuardian
+    );
+    require!(proposal.status == ProposalStatus::Queued, ReputationError::ProposalNotQueued);
+    require!(clock.unix_timestamp < proposal.eta, ReputationError::VetoWindowClosed);
+    
+    // A veto cancels the proposal; vetoed_by tells it apart from a proposer cancel
+    proposal.status = ProposalStatus::Cancelled;
+    proposal.vetoed_by = ctx.accounts.guardian.key();
+    proposal.vetoed_at = clock.unix_timestamp;
+    proposal.cancelled_at = clock.unix_timestamp;
+    
+    msg!("Proposal {} vetoed by guardian {}", proposal.key(), proposal.vetoed_by);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetGuardians<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.authority == authority.key()
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+/// Replace the guardian set (config authority, normally the governance PDA)
+pub fn set_guardians(ctx: Context<SetGuardians>, guardians: Vec<Pubkey>) -> Result<()> {
+    require!(
+        guardians.len() <= ProtocolConfig::MAX_GUARDIANS,
+        ReputationError::InvalidParameter
+    );
+    
+    let config = &mut ctx.accounts.config;
+    config.guardians = [Pubkey::default(); ProtocolConfig::MAX_GUARDIANS];
+    config.guardians[..guardians.len()].copy_from_slice(&guardians);
+    config.guardian_count = guardians.len() as u8;
+    
+    msg!("Guardian set updated: {} guardians", config.guardian_count);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct FinalizeProposal<'info> {
+    pub caller: Signer<'info>,
+    
+    #[account(mut)]
+    pub proposal: Account<'info, GovernanceProposal>,
+}
+
+/// Record the outcome of a closed vote, or expire a proposal nobody executed in time
+pub fn finalize_proposal(ctx: Context<FinalizeProposal>) -> Result<()> {
+    let proposal = &mut ctx.accounts.proposal;
+    let now = Clock::get()?.unix_timestamp;
+    
+    proposal.status = match proposal.status {
+        ProposalStatus::Active => {
+            require!(now >= proposal.voting_ends_at, ReputationError::VotingPeriodActive);
+            if proposal.passed(now) {
+                ProposalStatus::Succeeded
+            } else {
+                ProposalStatus::Defeated
+            }
+        }
+        ProposalStatus::Succeeded => {
+            require!(
+                now > proposal.voting_ends_at.saturating_add(EXECUTION_GRACE_PERIOD),
+                ReputationError::InvalidProposalStatus
+            );
+            ProposalStatus::Expired
+        }
+        ProposalStatus::Queued => {
+            require!(
+                now > proposal.eta.saturating_add(EXECUTION_GRACE_PERIOD),
+                ReputationError::InvalidProposalStatus
+            );
+            ProposalStatus::Expired
+        }
+        _ => return err!(ReputationError::InvalidProposalStatus),
+    };
+    proposal.finalized_at = now;
+    
+    msg!("Proposal {} finalized: for {} against {} abstain {} quorum {}",
+        proposal.key(), proposal.tally(VoteChoice::For, now), proposal.tally(VoteChoice::Against, now),
+        proposal.tally(VoteChoice::Abstain, now), proposal.quorum);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    pub proposer: Signer<'info>,
+    
+    #[account(
+        seeds = [b"agent", proposer_profile.agent_id.as_ref()],
+        bump = proposer_profile.bump,
+        constraint = is_authorized_signer(&proposer_profile, &proposer.key(), session.as_deref(), SCOPE_PROPOSE)?
+    )]
+    pub proposer_profile: Account<'info, crate::state::AgentProfile>,
+    
+    #[account(
+        mut,
+        constraint = proposal.proposer == proposer_profile.agent_id
+    )]
+    pub proposal: Account<'info, GovernanceProposal>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
+}
+
+/// Withdraw a proposal while it is still being voted on (the proposal fee is not refunded)
+pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+    let proposal = &mut ctx.accounts.proposal;
+    
+    require!(proposal.status == ProposalStatus::Active, ReputationError::InvalidProposalStatus);
+    
+    proposal.status = ProposalStatus::Cancelled;
+    proposal.cancelled_at = Clock::get()?.unix_timestamp;
+    
+    msg!("Proposal {} cancelled by proposer", proposal.key());
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CloseProposal<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    
+    #[account(
+        seeds = [b"agent", proposer_profile.agent_id.as_ref()],
+        bump = proposer_profile.bump,
+        constraint = is_authorized_signer(&proposer_profile, &proposer.key(), session.as_deref(), SCOPE_PROPOSE)?
+    )]
+    pub proposer_profile: Account<'info, crate::state::AgentProfile>,
+    
+    #[account(
+        mut,
+        close = proposer,
+        constraint = proposal.proposer == proposer_profile.agent_id
+    )]
+    pub proposal: Account<'info, GovernanceProposal>,
+    
+    /// CHECK: Instruction PDA, required for ExecuteInstruction proposals whether or
+    /// not one was attached; closed if it was
+    #[account(
+        mut,
+        seeds = [b"proposal_ix", proposal.key().as_ref()],
+        bump,
+    )]
+    pub proposal_instruction: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Batch PDA, required for Batch proposals; closed if one was attached
+    #[account(
+        mut,
+        seeds = [b"proposal_batch", proposal.key().as_ref()],
+        bump,
+    )]
+    pub proposal_batch: Option<UncheckedAccount<'info>>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
+}
+
+/// Close a finished proposal (and its stored instruction or batch) to reclaim rent
+pub fn close_proposal(ctx: Context<CloseProposal>) -> Result<()> {
+    let proposal = &ctx.accounts.proposal;
+    require!(proposal.status.is_final(), ReputationError::InvalidProposalStatus);
+    require!(proposal.deposit_settled, ReputationError::DepositNotSettled);
+    require!(
+        proposal.proposal_type != ProposalType::ExecuteInstruction
+            || ctx.accounts.proposal_instruction.is_some(),
+        ReputationError::ProposalInstructionMissing
+    );
+    require!(
+        proposal.proposal_type != ProposalType::Batch || ctx.accounts.proposal_batch.is_some(),
+        ReputationError::ProposalBatchMissing
+    );
+    
+    let proposer = ctx.accounts.proposer.to_account_info();
+    for attachment in [&ctx.accounts.proposal_instruction, &ctx.accounts.proposal_batch].into_iter().flatten() {
+        if attachment.owner == ctx.program_id && !attachment.data_is_empty() {
+            close_program_account(attachment, &proposer)?;
+        }
+    }
+    
+    msg!("Proposal {} closed", ctx.accounts.proposal.key());
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SettleProposalDeposit<'info> {
+    pub settler: Signer<'info>,
+    
+    #[account(mut)]
+    pub proposal: Account<'info, GovernanceProposal>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        mut,
+        associated_token::mint = config.reputation_mint,
+        associated_token::authority = deposit_escrow,
+    )]
+    pub deposit_escrow_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: PDA that owns the deposit escrow account
+    #[account(
+        seeds = [b"escrow", proposal.key().as_ref()],
+        bump,
+    )]
+    pub deposit_escrow: UncheckedAccount<'info>,
+    
+    /// Token account the deposit was paid from
+    #[account(
+        mut,
+        address = proposal.deposit_from
+    )]
+    pub proposer_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(
+        mut,
+        address = treasury.token_account
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", proposal.proposer.as_ref()],
+        bump = proposer_profile.bump,
+    )]
+    pub proposer_profile: Account<'info, crate::state::AgentProfile>,
+    
+    pub token_program: Program<'info, token::Token>,
+}
+
+/// Return a final proposal's deposit to the account that paid it if it reached quorum,
+/// otherwise (or if a guardian vetoed it) forfeit it to the treasury
+/// Permissionless, so forfeits don't depend on the proposer
+pub fn settle_proposal_deposit(ctx: Context<SettleProposalDeposit>) -> Result<()> {
+    let proposal = &ctx.accounts.proposal;
+    require!(proposal.status.is_final(), ReputationError::InvalidProposalStatus);
+    require!(!proposal.deposit_settled, ReputationError::DepositAlreadySettled);
+    
+    let amount = proposal.deposit;
+    let refund = proposal.vetoed_at == 0 && proposal.participation >= proposal.quorum;
+    let proposal_key = proposal.key();
+    let seeds = &[b"escrow", proposal_key.as_ref(), &[ctx.bumps.deposit_escrow]];
+    let signer = &[&seeds[..]];
+    
+    if amount == 0 {
+        // Nothing escrowed, settling only releases the proposer's open proposal
+    } else if refund {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.deposit_escrow_token_account.to_account_info(),
+                    to: ctx.accounts.proposer_token_account.to_account_info(),
+                    authority: ctx.accounts.deposit_escrow.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+    } else {
+        deposit_to_treasury(
+            &mut ctx.accounts.treasury,
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.deposit_escrow_token_account.to_account_info(),
+                    to: ctx.accounts.treasury_token_account.to_account_info(),
+                    authority: ctx.accounts.deposit_escrow.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+    }
+    
+    ctx.accounts.proposal.deposit = 0;
+    ctx.accounts.proposal.deposit_settled = true;
+    
+    let proposer_profile = &mut ctx.accounts.proposer_profile;
+    proposer_profile.open_proposals = proposer_profile.open_proposals.saturating_sub(1);
+    
+    msg!("Proposal {} deposit of {} {}", 
+        proposal_key, amount, if refund { "refunded" } else { "forfeited" });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CloseVoteRecord<'info> {
+    pub caller: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = rent_recipient,
+        seeds = [b"vote", vote_record.proposal.as_ref(), vote_record.voter.as_ref()],
+        bump = vote_record.bump,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+    
+    /// CHECK: Whoever paid for the record, refunded its rent
+    #[account(
+        mut,
+        address = vote_record.payer
+    )]
+    pub rent_recipient: UncheckedAccount<'info>,
+    
+    /// CHECK: The voted-on proposal; may already be closed
+    #[account(address = vote_record.proposal)]
+    pub proposal: UncheckedAccount<'info>,
+}
+
+/// Close a vote record once its proposal is finished or closed, refunding the
+/// rent to whoever paid for it. Anyone can close it, so records of deregistered
+/// agents don't stay open forever
+pub fn close_vote_record(ctx: Context<CloseVoteRecord>) -> Result<()> {
+    let proposal_info = ctx.accounts.proposal.to_account_info();
+    if !proposal_info.data_is_empty() {
+        let data = proposal_info.try_borrow_data()?;
+        let proposal = GovernanceProposal::try_deserialize(&mut &data[..])?;
+        require!(proposal.status.is_final(), ReputationError::InvalidProposalStatus);
+    }
+    
+    msg!("Vote record closed for {}", ctx.accounts.vote_record.voter);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ProtocolConfig {
+        let mut config = ProtocolConfig::deserialize(&mut &[0u8; ProtocolConfig::LEN][..]).unwrap();
+        config.min_proposer_reputation = 1000;
+        config
+    }
+
+    fn conviction_proposal() -> GovernanceProposal {
+        let mut proposal = GovernanceProposal::deserialize(&mut &[0u8; GovernanceProposal::LEN][..]).unwrap();
+        proposal.voting_mode = VotingMode::Conviction;
+        proposal.snapshot_timestamp = 1_000;
+        proposal.voting_ends_at = 1_000 + 86400;
+        proposal
+    }
+
+    #[test]
+    fn conviction_accrues_from_voted_at_until_voting_ends() {
+        let mut proposal = conviction_proposal();
+        proposal.add_votes(VoteChoice::For, 1000, 1_000);
+        assert_eq!(proposal.tally(VoteChoice::For, 1_000), 1000);
+        assert_eq!(proposal.tally(VoteChoice::For, 1_000 + 43200), 2000);
+        assert_eq!(proposal.tally(VoteChoice::For, proposal.voting_ends_at), 3000);
+        assert_eq!(proposal.tally(VoteChoice::For, proposal.voting_ends_at + 86400), 3000);
+        
+        // A vote cast halfway through has only held for the second half
+        proposal.add_votes(VoteChoice::Against, 1000, 1_000 + 43200);
+        assert_eq!(proposal.tally(VoteChoice::Against, 1_000 + 43200), 1000);
+        assert_eq!(proposal.tally(VoteChoice::Against, proposal.voting_ends_at), 2000);
+        assert!(proposal.tally(VoteChoice::For, proposal.voting_ends_at)
+            > proposal.tally(VoteChoice::Against, proposal.voting_ends_at));
+    }
+
+    #[test]
+    fn changed_votes_restart_conviction() {
+        let mut proposal = conviction_proposal();
+        proposal.add_votes(VoteChoice::For, 1000, 1_000);
+        proposal.remove_votes(VoteChoice::For, 1000, 1_000);
+        proposal.add_votes(VoteChoice::Against, 1000, 1_000 + 43200);
+        assert_eq!(proposal.tally(VoteChoice::For, proposal.voting_ends_at), 0);
+        assert_eq!(proposal.tally(VoteChoice::Against, proposal.voting_ends_at), 2000);
+    }
+
+    #[test]
+    fn other_modes_do_not_accrue() {
+        let mut proposal = conviction_proposal();
+        proposal.voting_mode = VotingMode::Quadratic;
+        let weight = proposal.vote_weight(10_000);
+        assert_eq!(weight, 100);
+        proposal.add_votes(VoteChoice::For, weight, 1_000);
+        assert_eq!(proposal.tally(VoteChoice::For, proposal.voting_ends_at), 100);
+    }
+
+    #[test]
+    fn unbounded_values_are_rejected_for_every_parameter() {
+        let parameters = [
+            ProposalType::UpdateMinReputationForVouching,
+            ProposalType::UpdateDecayRate,
+            ProposalType::UpdateVouchLockupPeriod,
+            ProposalType::UpdateSlashThreshold,
+            ProposalType::UpdateMaxTrustMultiplier,
+            ProposalType::UpdateTaskFraudSlashRate,
+            ProposalType::UpdateCollusionSlashRate,
+            ProposalType::UpdateMaliciousSlashRate,
+            ProposalType::UpdateVouchFee,
+            ProposalType::UpdateProposalFee,
+            ProposalType::UpdateVotingPeriod,
+            ProposalType::UpdateQuorumVotes,
+            ProposalType::UpdateQuorumBps,
+            ProposalType::UpdateMajorityBps,
+            ProposalType::UpdateMinProposerReputation,
+            ProposalType::UpdateMinVoterReputation,
+            ProposalType::UpdateTimelockDelay,
+            ProposalType::UpdateAllowedVotingModes,
+            ProposalType::UpdateProposalDeposit,
+        ];
+        for parameter in parameters {
+            let mut config = config();
+            for value in [u64::MAX, i64::MAX as u64 + 1] {
+                assert!(
+                    apply_parameter_change(&mut config, &parameter, value).is_err(),
+                    "{:?} accepted {}", parameter, value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn durations_must_fit_their_range() {
+        let mut config = config();
+        assert!(apply_parameter_change(&mut config, &ProposalType::UpdateVouchLockupPeriod, 86400).is_ok());
+        assert_eq!(config.vouch_lockup_period, 86400);
+        let too_long = (ProtocolConfig::MAX_VOUCH_LOCKUP_PERIOD + 1) as u64;
+        assert!(apply_parameter_change(&mut config, &ProposalType::UpdateVouchLockupPeriod, too_long).is_err());
+        assert!(apply_parameter_change(&mut config, &ProposalType::UpdateVotingPeriod, 60).is_err());
+        assert_eq!(config.vouch_lockup_period, 86400);
+    }
+
+    #[test]
+    fn proposal_costs_are_capped() {
+        let mut config = config();
+        assert!(apply_parameter_change(&mut config, &ProposalType::UpdateProposalFee, ProtocolConfig::MAX_PROPOSAL_FEE).is_ok());
+        assert!(apply_parameter_change(&mut config, &ProposalType::UpdateProposalFee, ProtocolConfig::MAX_PROPOSAL_FEE + 1).is_err());
+        assert!(apply_parameter_change(&mut config, &ProposalType::UpdateProposalDeposit, ProtocolConfig::MAX_PROPOSAL_DEPOSIT).is_ok());
+        assert!(apply_parameter_change(&mut config, &ProposalType::UpdateProposalDeposit, ProtocolConfig::MAX_PROPOSAL_DEPOSIT + 1).is_err());
+        assert_eq!(config.proposal_fee, ProtocolConfig::MAX_PROPOSAL_FEE);
+        assert_eq!(config.proposal_deposit, ProtocolConfig::MAX_PROPOSAL_DEPOSIT);
+    }
+}
@@ -33,6 +33,30 @@ pub fn initialize(ctx: Context<Initialize>, config: ProtocolConfig) -> Result<()
     config_account.vouch_lockup_period = config.vouch_lockup_period;
     config_account.slash_threshold = 2000; // Default 20% slash
     config_account.max_trust_multiplier = 30000; // Default 3x max multiplier
+    config_account.task_fraud_slash_bps = 3000; // Default 30% slash
+    config_account.collusion_slash_bps = 4000; // Default 40% slash
+    config_account.malicious_slash_bps = 5000; // Default 50% slash
+    config_account.vouch_fee_bps = 100; // Default 1% vouch fee
+    config_account.proposal_fee = 100; // Default 100 token proposal fee
+    config_account.proposal_deposit = 500; // Default 500 token refundable deposit
+    config_account.voting_period = 86400 * 3; // Default 3 day voting period
+    config_account.quorum_votes = 10000; // Default 10000 reputation quorum
+    config_account.quorum_bps = 0; // Relative quorum off by default
+    config_account.majority_bps = 5000; // Default simple majority
+    config_account.min_proposer_reputation = 1000;
+    config_account.min_voter_reputation = 100;
+    config_account.total_reputation = 0;
+    config_account.timelock_delay = 86400 * 2; // Default 2 day timelock
+    // Optional guardian set supplied at init
+    require!(
+        config.guardian_count as usize <= ProtocolConfig::MAX_GUARDIANS,
+        ReputationError::InvalidParameter
+    );
+    config_account.guardians = config.guardians;
+    config_account.guardian_count = config.guardian_count;
+    config_account.allowed_voting_modes = ProtocolConfig::ALL_VOTING_MODES;
+    config_account.proposal_count = 0;
+    config_account.last_snapshot_slot = 0;
     config_account.bump = ctx.bumps.config;
     
     msg!("Protocol initialized with decay rate: {} bps, slash threshold: {} bps", 
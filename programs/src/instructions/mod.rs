@@ -9,6 +9,13 @@ pub mod governance;
 pub mod oracle;
 pub mod reputation_nft;
 pub mod zk_verification;
+pub mod dispute;
+pub mod treasury;
+pub mod delegation;
+pub mod session;
+pub mod deregister;
+pub mod name_registry;
+pub mod organization;
 
 pub use initialize::*;
 pub use register_agent::*;
@@ -21,3 +28,10 @@ pub use governance::*;
 pub use oracle::*;
 pub use reputation_nft::*;
 pub use zk_verification::*;
+pub use dispute::*;
+pub use treasury::*;
+pub use delegation::*;
+pub use session::*;
+pub use deregister::*;
+pub use name_registry::*;
+pub use organization::*;
@@ -0,0 +1,228 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use crate::state::AgentProfile;
+use crate::errors::ReputationError;
+
+// Agent name service
+// Each name has one AgentName PDA seeded by the hash of its normalized form, so
+// two agents can't hold names that differ only in case. Clients resolve a name
+// by deriving the PDA and reading agent_id.
+
+pub const MIN_NAME_LEN: usize = 3;
+pub const MAX_NAME_LEN: usize = 50;
+
+#[account]
+pub struct AgentName {
+    pub agent_id: Pubkey, // Current holder
+    pub name: [u8; 50], // Normalized, zero-padded
+    pub name_len: u8,
+    pub registered_at: i64, // Claimed or last transferred
+    pub bump: u8,
+}
+
+impl AgentName {
+    pub const LEN: usize = 8 + 32 + 50 + 1 + 8 + 1;
+}
+
+/// PDA seed for a name: sha256 of its lowercased bytes, as seeds are capped at 32 bytes
+/// Doesn't validate; pair with normalize_name
+pub fn name_seed(name: &[u8; 50], name_len: u8) -> [u8; 32] {
+    let len = (name_len as usize).min(MAX_NAME_LEN);
+    hash(&name[..len].to_ascii_lowercase()).to_bytes()
+}
+
+/// Check a raw name and return its normalized (lowercase) form
+/// Must be UTF-8 and 3-50 characters of a-z, 0-9, '-' or '_', starting with a letter or digit
+pub fn normalize_name(name: &[u8; 50], name_len: u8) -> Result<[u8; 50]> {
+    let len = name_len as usize;
+    require!(len <= MAX_NAME_LEN, ReputationError::NameTooLong);
+    let name = core::str::from_utf8(&name[..len]).map_err(|_| ReputationError::InvalidNameEncoding)?;
+    require!(len >= MIN_NAME_LEN, ReputationError::InvalidAgentName);
+    
+    let mut normalized = [0u8; 50];
+    for (i, c) in name.chars().enumerate() {
+        let c = c.to_ascii_lowercase();
+        let allowed = c.is_ascii_lowercase()
+            || c.is_ascii_digit()
+            || (i > 0 && (c == '-' || c == '_'));
+        require!(allowed, ReputationError::InvalidAgentName);
+        normalized[i] = c as u8;
+    }
+    Ok(normalized)
+}
+
+/// Point `record` at `profile` and copy the display name (case kept) into the profile
+pub fn assign_name(
+    profile: &mut AgentProfile,
+    record: &mut AgentName,
+    display_name: &[u8; 50],
+    normalized: [u8; 50],
+    name_len: u8,
+    bump: u8,
+) -> Result<()> {
+    let len = name_len as usize;
+    profile.name = [0u8; 50];
+    profile.name[..len].copy_from_slice(&display_name[..len]);
+    profile.name_len = name_len;
+    
+    record.agent_id = profile.agent_id;
+    record.name = normalized;
+    record.name_len = name_len;
+    record.registered_at = Clock::get()?.unix_timestamp;
+    record.bump = bump;
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(name: [u8; 50], name_len: u8)]
+pub struct ClaimAgentName<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", agent_profile.agent_id.as_ref()],
+        bump = agent_profile.bump,
+        constraint = agent_profile.owner == owner.key()
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = AgentName::LEN,
+        seeds = [b"name", name_seed(&name, name_len).as_ref()],
+        bump
+    )]
+    pub agent_name: Account<'info, AgentName>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Claim a free name for an agent that holds none (owner only)
+pub fn claim_agent_name(ctx: Context<ClaimAgentName>, name: [u8; 50], name_len: u8) -> Result<()> {
+    require!(ctx.accounts.agent_profile.name_len == 0, ReputationError::AgentNameAlreadySet);
+    let normalized = normalize_name(&name, name_len)?;
+    
+    assign_name(
+        &mut ctx.accounts.agent_profile,
+        &mut ctx.accounts.agent_name,
+        &name,
+        normalized,
+        name_len,
+        ctx.bumps.agent_name,
+    )?;
+    
+    msg!("Agent {} claimed name {}",
+        ctx.accounts.agent_profile.agent_id, ctx.accounts.agent_name.key());
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ReleaseAgentName<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", agent_profile.agent_id.as_ref()],
+        bump = agent_profile.bump,
+        constraint = agent_profile.owner == owner.key()
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"name", name_seed(&agent_name.name, agent_name.name_len).as_ref()],
+        bump = agent_name.bump,
+        constraint = agent_name.agent_id == agent_profile.agent_id
+    )]
+    pub agent_name: Account<'info, AgentName>,
+}
+
+/// Give up the agent's name so anyone can claim it (owner only)
+pub fn release_agent_name(ctx: Context<ReleaseAgentName>) -> Result<()> {
+    let profile = &mut ctx.accounts.agent_profile;
+    profile.name = [0u8; 50];
+    profile.name_len = 0;
+    
+    msg!("Agent {} released name {}", profile.agent_id, ctx.accounts.agent_name.key());
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct TransferAgentName<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", agent_profile.agent_id.as_ref()],
+        bump = agent_profile.bump,
+        constraint = agent_profile.owner == owner.key()
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    
+    /// Owner of the receiving agent, who must accept the name
+    pub recipient_owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", recipient_profile.agent_id.as_ref()],
+        bump = recipient_profile.bump,
+        constraint = recipient_profile.owner == recipient_owner.key()
+    )]
+    pub recipient_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"name", name_seed(&agent_name.name, agent_name.name_len).as_ref()],
+        bump = agent_name.bump,
+        constraint = agent_name.agent_id == agent_profile.agent_id
+    )]
+    pub agent_name: Account<'info, AgentName>,
+}
+
+/// Move the agent's name to another agent that holds none (both owners sign)
+pub fn transfer_agent_name(ctx: Context<TransferAgentName>) -> Result<()> {
+    require!(
+        ctx.accounts.recipient_profile.agent_id != ctx.accounts.agent_profile.agent_id,
+        ReputationError::InvalidParameter
+    );
+    require!(ctx.accounts.recipient_profile.name_len == 0, ReputationError::AgentNameAlreadySet);
+    
+    let display_name = ctx.accounts.agent_profile.name;
+    let record = &ctx.accounts.agent_name;
+    let (normalized, name_len, bump) = (record.name, record.name_len, record.bump);
+    assign_name(
+        &mut ctx.accounts.recipient_profile,
+        &mut ctx.accounts.agent_name,
+        &display_name,
+        normalized,
+        name_len,
+        bump,
+    )?;
+    
+    let profile = &mut ctx.accounts.agent_profile;
+    profile.name = [0u8; 50];
+    profile.name_len = 0;
+    
+    msg!("Name {} transferred from {} to {}",
+        ctx.accounts.agent_name.key(), profile.agent_id, ctx.accounts.recipient_profile.agent_id);
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(name: [u8; 50], name_len: u8)]
+pub struct ResolveAgentName<'info> {
+    #[account(
+        seeds = [b"name", name_seed(&name, name_len).as_ref()],
+        bump = agent_name.bump,
+    )]
+    pub agent_name: Account<'info, AgentName>,
+}
+
+/// Look up the agent_id holding a name, in any letter case
+pub fn resolve_agent_name(ctx: Context<ResolveAgentName>, _name: [u8; 50], _name_len: u8) -> Result<Pubkey> {
+    Ok(ctx.accounts.agent_name.agent_id)
+}
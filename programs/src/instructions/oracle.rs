@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
 use crate::state::{AgentProfile, ProtocolConfig};
 use crate::errors::ReputationError;
+use crate::instructions::zk_verification::refresh_reputation_commitment;
 
 /// Oracle Integration for automated reputation updates
 /// Allows verified oracles to mint reputation for off-chain achievements
@@ -21,6 +22,7 @@ impl OracleRegistry {
 pub struct OracleAttestation {
     pub oracle: Pubkey,
     pub agent: Pubkey,
+    pub nonce: u64, // Per-agent sequence number, used in the PDA seeds
     pub attestation_type: AttestationType,
     pub metadata_hash: [u8; 32], // Hash of off-chain data
     pub reputation_amount: u64,
@@ -29,10 +31,10 @@ pub struct OracleAttestation {
 }
 
 impl OracleAttestation {
-    pub const LEN: usize = 8 + 32 + 32 + 1 + 32 + 8 + 8 + 1;
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1 + 32 + 8 + 8 + 1;
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
 pub enum AttestationType {
     GitHubCommit,        // Code contributions
     GitHubPRMerged,      // PRs merged
@@ -140,14 +142,20 @@ pub struct SubmitAttestation<'info> {
         space = OracleAttestation::LEN,
         seeds = [
             b"attestation",
-            oracle.key().as_ref(),
             agent.key().as_ref(),
-            &Clock::get()?.unix_timestamp.to_le_bytes()
+            &agent_profile.attestation_count.to_le_bytes()
         ],
         bump
     )]
     pub attestation: Account<'info, OracleAttestation>,
     
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
     pub system_program: Program<'info, System>,
 }
 
@@ -193,6 +201,7 @@ pub fn submit_attestation(
     
     attestation.oracle = oracle;
     attestation.agent = ctx.accounts.agent.key();
+    attestation.nonce = ctx.accounts.agent_profile.attestation_count;
     attestation.attestation_type = attestation_type.clone();
     attestation.metadata_hash = metadata_hash;
     attestation.reputation_amount = reputation_amount;
@@ -200,58 +209,19 @@ pub fn submit_attestation(
     attestation.bump = ctx.bumps.attestation;
     
     // Apply reputation to agent
+    let before = ctx.accounts.agent_profile.reputation_score;
     ctx.accounts.agent_profile.reputation_score = 
         ctx.accounts.agent_profile.reputation_score.saturating_add(reputation_amount);
+    ctx.accounts.config.track_reputation_change(before, ctx.accounts.agent_profile.reputation_score);
     ctx.accounts.agent_profile.total_tasks_completed = 
         ctx.accounts.agent_profile.total_tasks_completed.saturating_add(1);
+    ctx.accounts.agent_profile.attestation_count = 
+        ctx.accounts.agent_profile.attestation_count.saturating_add(1);
     ctx.accounts.agent_profile.last_activity_timestamp = clock.unix_timestamp;
+    refresh_reputation_commitment(&mut ctx.accounts.agent_profile, ctx.accounts.config.last_snapshot_slot)?;
     
     msg!("Oracle attestation: {} earned {} reputation for {:?}", 
         attestation.agent, reputation_amount, attestation_type);
     
     Ok(())
 }
-
-/// Claim reputation from multiple attestations (batch processing)
-#[derive(Accounts)]
-pub struct ClaimReputation<'info> {
-    #[account(mut)]
-    pub agent: Signer<'info>,
-    
-    #[account(
-        mut,
-        seeds = [b"agent", agent.key().as_ref()],
-        bump = agent_profile.bump,
-    )]
-    pub agent_profile: Account<'info, AgentProfile>,
-}
-
-pub fn claim_reputation(ctx: Context<ClaimReputation>) -> Result<()> {
-    // In production: This would process pending attestations
-    // and mint reputation tokens to the agent
-    
-    msg!("Reputation claimed for {}", ctx.accounts.agent.key());
-    Ok(())
-}
-
-/// Get attestation metadata URI (for off-chain verification)
-/// Returns fixed-size array instead of String for Anchor compatibility
-pub fn get_attestation_uri(metadata_hash: [u8; 32]) -> [u8; 100] {
-    // Convert hash to hex string stored in fixed array
-    let mut result = [0u8; 100];
-    let prefix = b"https://api.agentreputation.io/attestations/";
-    result[..prefix.len()].copy_from_slice(prefix);
-    
-    // Add hex representation of hash (64 chars for 32 bytes)
-    for (i, byte) in metadata_hash.iter().enumerate() {
-        let hex_chars = format!("{:02x}", byte);
-        let bytes = hex_chars.as_bytes();
-        let pos = prefix.len() + i * 2;
-        if pos + 1 < 100 {
-            result[pos] = bytes[0];
-            result[pos + 1] = bytes[1];
-        }
-    }
-    
-    result
-}
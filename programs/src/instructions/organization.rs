@@ -0,0 +1,451 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, TokenAccount, Transfer};
+use crate::state::{AgentProfile, ProtocolConfig};
+use crate::errors::ReputationError;
+
+/// Org stake escrow PDA seed, followed by the organization's address
+pub const ORG_STAKE_ESCROW_SEED: &[u8] = b"org_stake";
+
+/// Organizations
+/// An operator groups its agents under one Organization. Membership needs both the
+/// org authority and the agent owner to sign. The org keeps aggregate reputation,
+/// task and slash figures over its members, refreshed by sync_org_member and on
+/// every slash, and can back its members with a token stake that absorbs a share
+/// (liability_bps) of each member's slash.
+
+#[account]
+pub struct Organization {
+    pub authority: Pubkey, // Creating key; admits and removes members
+    pub name: [u8; 50],
+    pub member_count: u32,
+    pub total_reputation: u64, // Sum of member reputation as of their last sync
+    pub total_tasks_completed: u64, // Sum of member tasks as of their last sync
+    pub slash_count: u64, // Members slashed while in the org
+    pub reputation_slashed: u64, // Reputation those slashes took
+    pub last_slashed_at: i64,
+    pub stake: u64, // Tokens held in the org stake escrow
+    pub stake_slashed: u64,
+    pub liability_bps: u64, // Share of a member's slash rate applied to the stake; 0 = none
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl Organization {
+    pub const LEN: usize = 8 + 32 + 50 + 4 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1;
+    
+    /// Replace a member's last snapshot in the aggregates with its current figures
+    pub fn sync_member(&mut self, membership: &mut OrgMembership, profile: &AgentProfile) {
+        self.total_reputation = self.total_reputation
+            .saturating_sub(membership.reputation)
+            .saturating_add(profile.reputation_score);
+        self.total_tasks_completed = self.total_tasks_completed
+            .saturating_sub(membership.tasks_completed)
+            .saturating_add(profile.total_tasks_completed);
+        membership.reputation = profile.reputation_score;
+        membership.tasks_completed = profile.total_tasks_completed;
+    }
+    
+    /// Stake charged when a member is slashed at `slash_bps`
+    pub fn stake_liability(&self, slash_bps: u64) -> u64 {
+        ((self.stake as u128)
+            .saturating_mul(slash_bps as u128)
+            .saturating_mul(self.liability_bps as u128)
+            / 100_000_000) as u64
+    }
+}
+
+#[account]
+pub struct OrgMembership {
+    pub organization: Pubkey,
+    pub agent_id: Pubkey,
+    pub reputation: u64, // Snapshot counted in the org aggregates
+    pub tasks_completed: u64,
+    pub slash_count: u32, // Slashes while a member
+    pub joined_at: i64,
+    pub bump: u8,
+}
+
+impl OrgMembership {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 4 + 8 + 1;
+}
+
+#[derive(Accounts)]
+pub struct CreateOrganization<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = Organization::LEN,
+        seeds = [b"organization", authority.key().as_ref()],
+        bump
+    )]
+    pub organization: Account<'info, Organization>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_organization(
+    ctx: Context<CreateOrganization>,
+    name: [u8; 50],
+    name_len: u8,
+    liability_bps: u64,
+) -> Result<()> {
+    require!(name_len <= 50, ReputationError::NameTooLong);
+    require!(liability_bps <= 10000, ReputationError::InvalidParameter);
+    
+    let org = &mut ctx.accounts.organization;
+    org.authority = ctx.accounts.authority.key();
+    org.name = name;
+    org.member_count = 0;
+    org.total_reputation = 0;
+    org.total_tasks_completed = 0;
+    org.slash_count = 0;
+    org.reputation_slashed = 0;
+    org.last_slashed_at = 0;
+    org.stake = 0;
+    org.stake_slashed = 0;
+    org.liability_bps = liability_bps;
+    org.created_at = Clock::get()?.unix_timestamp;
+    org.bump = ctx.bumps.organization;
+    
+    msg!("Organization {} created, liability {}bps", org.key(), liability_bps);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct JoinOrganization<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"organization", authority.key().as_ref()],
+        bump = organization.bump,
+    )]
+    pub organization: Account<'info, Organization>,
+    
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", agent_profile.agent_id.as_ref()],
+        bump = agent_profile.bump,
+        constraint = agent_profile.owner == owner.key()
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = OrgMembership::LEN,
+        seeds = [b"org_member", organization.key().as_ref(), agent_profile.agent_id.as_ref()],
+        bump
+    )]
+    pub membership: Account<'info, OrgMembership>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Add an agent to an organization; the org authority and the agent owner both sign
+pub fn join_organization(ctx: Context<JoinOrganization>) -> Result<()> {
+    require!(
+        ctx.accounts.agent_profile.organization == Pubkey::default(),
+        ReputationError::AlreadyOrgMember
+    );
+    
+    let org = &mut ctx.accounts.organization;
+    let profile = &mut ctx.accounts.agent_profile;
+    let membership = &mut ctx.accounts.membership;
+    membership.organization = org.key();
+    membership.agent_id = profile.agent_id;
+    membership.reputation = 0;
+    membership.tasks_completed = 0;
+    membership.slash_count = 0;
+    membership.joined_at = Clock::get()?.unix_timestamp;
+    membership.bump = ctx.bumps.membership;
+    
+    org.sync_member(membership, profile);
+    org.member_count = org.member_count.saturating_add(1);
+    profile.organization = org.key();
+    
+    msg!("Agent {} joined organization {}", profile.agent_id, org.key());
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct LeaveOrganization<'info> {
+    /// The org authority or the agent owner
+    pub signer: Signer<'info>,
+    
+    /// CHECK: Receives the membership rent it paid at join
+    #[account(
+        mut,
+        address = organization.authority
+    )]
+    pub authority: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"organization", organization.authority.as_ref()],
+        bump = organization.bump,
+    )]
+    pub organization: Account<'info, Organization>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", agent_profile.agent_id.as_ref()],
+        bump = agent_profile.bump,
+        constraint = signer.key() == organization.authority || signer.key() == agent_profile.owner
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"org_member", organization.key().as_ref(), agent_profile.agent_id.as_ref()],
+        bump = membership.bump,
+    )]
+    pub membership: Account<'info, OrgMembership>,
+}
+
+/// Remove an agent from its organization (either side can end it)
+/// Not while a dispute against the agent is open, so liability can't be dodged
+pub fn leave_organization(ctx: Context<LeaveOrganization>) -> Result<()> {
+    require!(ctx.accounts.agent_profile.open_disputes == 0, ReputationError::AgentHasOpenObligations);
+    
+    let org = &mut ctx.accounts.organization;
+    let membership = &ctx.accounts.membership;
+    org.total_reputation = org.total_reputation.saturating_sub(membership.reputation);
+    org.total_tasks_completed = org.total_tasks_completed.saturating_sub(membership.tasks_completed);
+    org.member_count = org.member_count.saturating_sub(1);
+    ctx.accounts.agent_profile.organization = Pubkey::default();
+    
+    msg!("Agent {} left organization {}", membership.agent_id, org.key());
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SyncOrgMember<'info> {
+    #[account(
+        mut,
+        seeds = [b"organization", organization.authority.as_ref()],
+        bump = organization.bump,
+    )]
+    pub organization: Account<'info, Organization>,
+    
+    #[account(
+        seeds = [b"agent", agent_profile.agent_id.as_ref()],
+        bump = agent_profile.bump,
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"org_member", organization.key().as_ref(), agent_profile.agent_id.as_ref()],
+        bump = membership.bump,
+    )]
+    pub membership: Account<'info, OrgMembership>,
+}
+
+/// Refresh a member's reputation and tasks in the org aggregates
+/// Permissionless, like apply_decay
+pub fn sync_org_member(ctx: Context<SyncOrgMember>) -> Result<()> {
+    let org = &mut ctx.accounts.organization;
+    org.sync_member(&mut ctx.accounts.membership, &ctx.accounts.agent_profile);
+    
+    msg!("Organization {}: {} members, total reputation {}, {} tasks",
+        org.key(), org.member_count, org.total_reputation, org.total_tasks_completed);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct FundOrgStake<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"organization", authority.key().as_ref()],
+        bump = organization.bump,
+    )]
+    pub organization: Account<'info, Organization>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        mut,
+        associated_token::mint = config.reputation_mint,
+        associated_token::authority = authority,
+    )]
+    pub authority_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        associated_token::mint = config.reputation_mint,
+        associated_token::authority = stake_escrow,
+    )]
+    pub stake_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: PDA that owns the org stake account
+    #[account(
+        seeds = [ORG_STAKE_ESCROW_SEED, organization.key().as_ref()],
+        bump,
+    )]
+    pub stake_escrow: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, token::Token>,
+}
+
+/// Add to the stake backing the organization's members
+pub fn fund_org_stake(ctx: Context<FundOrgStake>, amount: u64) -> Result<()> {
+    require!(amount > 0, ReputationError::InvalidParameter);
+    
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.authority_token_account.to_account_info(),
+                to: ctx.accounts.stake_token_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+    
+    let org = &mut ctx.accounts.organization;
+    org.stake = org.stake.saturating_add(amount);
+    
+    msg!("Organization {} stake now {}", org.key(), org.stake);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct WithdrawOrgStake<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"organization", authority.key().as_ref()],
+        bump = organization.bump,
+    )]
+    pub organization: Account<'info, Organization>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        mut,
+        associated_token::mint = config.reputation_mint,
+        associated_token::authority = authority,
+    )]
+    pub authority_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        associated_token::mint = config.reputation_mint,
+        associated_token::authority = stake_escrow,
+    )]
+    pub stake_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: PDA that owns the org stake account
+    #[account(
+        seeds = [ORG_STAKE_ESCROW_SEED, organization.key().as_ref()],
+        bump,
+    )]
+    pub stake_escrow: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, token::Token>,
+}
+
+/// Withdraw the org stake; only once every member has left
+pub fn withdraw_org_stake(ctx: Context<WithdrawOrgStake>) -> Result<()> {
+    require!(ctx.accounts.organization.member_count == 0, ReputationError::OrgHasMembers);
+    
+    let amount = ctx.accounts.organization.stake;
+    let org_key = ctx.accounts.organization.key();
+    let seeds = &[ORG_STAKE_ESCROW_SEED, org_key.as_ref(), &[ctx.bumps.stake_escrow]];
+    let signer = &[&seeds[..]];
+    
+    if amount > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.stake_token_account.to_account_info(),
+                    to: ctx.accounts.authority_token_account.to_account_info(),
+                    authority: ctx.accounts.stake_escrow.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+    }
+    ctx.accounts.organization.stake = 0;
+    
+    msg!("Organization {} withdrew {} stake", org_key, amount);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct VerifyOrgMembership<'info> {
+    #[account(
+        seeds = [b"agent", agent.key().as_ref()],
+        bump = agent_profile.bump,
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    
+    /// CHECK: The agent being checked
+    pub agent: UncheckedAccount<'info>,
+    
+    /// The agent's organization; may be omitted if it has none
+    pub organization: Option<Account<'info, Organization>>,
+}
+
+/// Whether the agent belongs to an organization with at least `min_org_reputation`
+/// aggregate reputation and at most `max_slash_count` member slashes
+pub fn verify_org_membership(
+    ctx: Context<VerifyOrgMembership>,
+    min_org_reputation: u64,
+    max_slash_count: u64,
+) -> Result<bool> {
+    let profile = &ctx.accounts.agent_profile;
+    if profile.organization == Pubkey::default() {
+        return Ok(false);
+    }
+    let org = ctx.accounts.organization.as_ref().ok_or(ReputationError::InvalidOrganization)?;
+    require!(org.key() == profile.organization, ReputationError::InvalidOrganization);
+    
+    let reputable = org.total_reputation >= min_org_reputation && org.slash_count <= max_slash_count;
+    msg!("Verification: {} is in organization {} (reputation {}, {} slashes): {}",
+        profile.agent_id, org.key(), org.total_reputation, org.slash_count, reputable);
+    Ok(reputable)
+}
+
+/// Check that an org stake escrow PDA and token account belong to `organization`
+/// Used when the escrow is passed as an optional account; returns the escrow bump
+pub fn verify_org_stake_escrow(
+    program_id: &Pubkey,
+    organization: &Pubkey,
+    escrow: &AccountInfo,
+    escrow_token_account: &TokenAccount,
+    reputation_mint: &Pubkey,
+) -> Result<u8> {
+    let (escrow_key, escrow_bump) = Pubkey::find_program_address(
+        &[ORG_STAKE_ESCROW_SEED, organization.as_ref()],
+        program_id,
+    );
+    require!(escrow.key() == escrow_key, ReputationError::InvalidParameter);
+    require!(
+        escrow_token_account.owner == escrow_key && escrow_token_account.mint == *reputation_mint,
+        ReputationError::InvalidParameter
+    );
+    Ok(escrow_bump)
+}
@@ -1,32 +1,68 @@
 use anchor_lang::prelude::*;
-use crate::state::AgentProfile;
+use crate::state::{AgentMetadata, AgentProfile, ReputationCheckpoint};
 use crate::errors::ReputationError;
+use crate::instructions::zk_verification::refresh_reputation_commitment;
+use crate::instructions::name_registry::{AgentName, name_seed, normalize_name, assign_name};
 
 #[derive(Accounts)]
+#[instruction(agent_name_bytes: [u8; 50], name_len: u8)]
 pub struct RegisterAgent<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
     
+    /// Signs once so nobody can register an identity whose key they don't hold
+    pub agent_id: Signer<'info>,
+    
     #[account(
         init,
         payer = owner,
         space = AgentProfile::LEN,
-        seeds = [b"agent", owner.key().as_ref()],
+        seeds = [b"agent", agent_id.key().as_ref()],
         bump
     )]
     pub agent_profile: Account<'info, AgentProfile>,
     
+    /// CHECK: Must be empty; a tombstone means this identity was deregistered
+    #[account(
+        seeds = [b"tombstone", agent_id.key().as_ref()],
+        bump,
+    )]
+    pub tombstone: UncheckedAccount<'info>,
+    
+    /// Name record, omitted (with name_len 0) to register without a name and
+    /// claim_agent_name later
+    #[account(
+        init,
+        payer = owner,
+        space = AgentName::LEN,
+        seeds = [b"name", name_seed(&agent_name_bytes, name_len).as_ref()],
+        bump
+    )]
+    pub agent_name: Option<Account<'info, AgentName>>,
+    
     pub system_program: Program<'info, System>,
 }
 
-pub fn register_agent(ctx: Context<RegisterAgent>, agent_name_bytes: [u8; 50], name_len: u8) -> Result<()> {
-    require!(name_len <= 50, ReputationError::NameTooLong);
+/// Register a new agent under agent_id, a keypair that signs registration and
+/// is never needed again. The agent_id never changes; the signing owner becomes
+/// the owner and operating key, both of which can be rotated later
+pub fn register_agent(
+    ctx: Context<RegisterAgent>,
+    agent_name_bytes: [u8; 50],
+    name_len: u8,
+) -> Result<()> {
+    require!(
+        ctx.accounts.agent_name.is_some() || name_len == 0,
+        ReputationError::InvalidAgentName
+    );
+    require!(ctx.accounts.tombstone.data_is_empty(), ReputationError::AgentDeregistered);
     
     let profile = &mut ctx.accounts.agent_profile;
     let clock = Clock::get()?;
     
+    profile.agent_id = ctx.accounts.agent_id.key();
     profile.owner = ctx.accounts.owner.key();
-    profile.name = agent_name_bytes;
+    profile.operating_key = ctx.accounts.owner.key();
     profile.reputation_score = 0;
     profile.total_tasks_completed = 0;
     profile.last_activity_timestamp = clock.unix_timestamp;
@@ -34,8 +70,102 @@ pub fn register_agent(ctx: Context<RegisterAgent>, agent_name_bytes: [u8; 50], n
     profile.positive_vouches = 0;
     profile.negative_vouches = 0;
     profile.staked_amount = 0;
+    profile.commitment_nonce = 0;
+    profile.checkpoints = [ReputationCheckpoint::default(); AgentProfile::MAX_CHECKPOINTS];
+    profile.checkpoint_count = 0;
+    profile.attestation_count = 0;
+    profile.proof_count = 0;
+    profile.dispute_count = 0;
+    profile.open_vouches = 0;
+    profile.open_disputes = 0;
+    profile.open_proposals = 0;
+    profile.organization = Pubkey::default();
     profile.bump = ctx.bumps.agent_profile;
+    profile.metadata = AgentMetadata::default();
+    profile.name = [0u8; 50];
+    profile.name_len = 0;
+    if let Some(agent_name) = ctx.accounts.agent_name.as_mut() {
+        let normalized = normalize_name(&agent_name_bytes, name_len)?;
+        assign_name(profile, agent_name, &agent_name_bytes, normalized, name_len, ctx.bumps.agent_name)?;
+    }
+    refresh_reputation_commitment(profile, 0)?; // A new history has nothing to keep
     
     msg!("Agent registered");
     Ok(())
 }
+
+#[derive(Accounts)]
+#[instruction(metadata: AgentMetadata)]
+pub struct UpdateAgentProfile<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", agent_profile.agent_id.as_ref()],
+        bump = agent_profile.bump,
+        constraint = agent_profile.owner == owner.key(),
+        realloc = AgentProfile::space(&metadata),
+        realloc::payer = owner,
+        realloc::zero = false,
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Replace the agent's metadata, growing or shrinking the account to fit
+pub fn update_agent_profile(ctx: Context<UpdateAgentProfile>, metadata: AgentMetadata) -> Result<()> {
+    require!(
+        metadata.service_endpoint.len() <= AgentMetadata::MAX_ENDPOINT_LEN
+            && metadata.framework.len() <= AgentMetadata::MAX_FRAMEWORK_LEN
+            && metadata.version.len() <= AgentMetadata::MAX_VERSION_LEN,
+        ReputationError::MetadataTooLong
+    );
+    // Empty, or a URI with a scheme and no whitespace
+    let endpoint = &metadata.service_endpoint;
+    require!(
+        endpoint.is_empty()
+            || (endpoint.contains("://") && !endpoint.chars().any(|c| c.is_whitespace() || c.is_control())),
+        ReputationError::InvalidServiceEndpoint
+    );
+    
+    let profile = &mut ctx.accounts.agent_profile;
+    profile.metadata = AgentMetadata {
+        updated_at: Clock::get()?.unix_timestamp,
+        ..metadata
+    };
+    
+    msg!("Agent profile updated: {}", profile.agent_id);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RotateAgentKeys<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", agent_profile.agent_id.as_ref()],
+        bump = agent_profile.bump,
+        constraint = agent_profile.owner == owner.key()
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+}
+
+/// Replace the owner and operating keys; the profile and everything keyed by
+/// agent_id stay where they are
+pub fn rotate_agent_keys(ctx: Context<RotateAgentKeys>, new_owner: Pubkey, new_operating_key: Pubkey) -> Result<()> {
+    require!(
+        new_owner != Pubkey::default() && new_operating_key != Pubkey::default(),
+        ReputationError::InvalidParameter
+    );
+    
+    let profile = &mut ctx.accounts.agent_profile;
+    profile.owner = new_owner;
+    profile.operating_key = new_operating_key;
+    
+    msg!("Agent {} keys rotated: owner {} operating key {}", 
+        profile.agent_id, new_owner, new_operating_key);
+    Ok(())
+}
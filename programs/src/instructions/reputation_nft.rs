@@ -1,6 +1,9 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Mint, MintTo, FreezeAccount};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Approve, Token, TokenAccount, Mint, MintTo, SetAuthority};
+use anchor_spl::token::spl_token::instruction::AuthorityType;
 use crate::state::AgentProfile;
+use crate::instructions::session::{SessionKey, is_authorized_signer, SCOPE_NFT};
 
 /// Soulbound Reputation NFT
 /// Non-transferable NFT representing agent's trust level
@@ -9,6 +12,7 @@ use crate::state::AgentProfile;
 #[account]
 pub struct ReputationNFT {
     pub agent: Pubkey,
+    pub mint: Pubkey,
     pub level: ReputationLevel,
     pub score_at_mint: u64,
     pub minted_at: i64,
@@ -17,10 +21,10 @@ pub struct ReputationNFT {
 }
 
 impl ReputationNFT {
-    pub const LEN: usize = 8 + 32 + 1 + 8 + 8 + 100 + 1;
+    pub const LEN: usize = 8 + 32 + 32 + 1 + 8 + 8 + 100 + 1;
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
 pub enum ReputationLevel {
     Novice,      // 0-100 rep
     Contributor, // 100-500 rep
@@ -57,8 +61,9 @@ pub struct MintReputationNFT<'info> {
     pub agent: Signer<'info>,
     
     #[account(
-        seeds = [b"agent", agent.key().as_ref()],
+        seeds = [b"agent", agent_profile.agent_id.as_ref()],
         bump = agent_profile.bump,
+        constraint = is_authorized_signer(&agent_profile, &agent.key(), session.as_deref(), SCOPE_NFT)?
     )]
     pub agent_profile: Account<'info, AgentProfile>,
     
@@ -66,7 +71,7 @@ pub struct MintReputationNFT<'info> {
         init,
         payer = agent,
         space = ReputationNFT::LEN,
-        seeds = [b"reputation_nft", agent.key().as_ref()],
+        seeds = [b"reputation_nft", agent_profile.agent_id.as_ref()],
         bump
     )]
     pub reputation_nft: Account<'info, ReputationNFT>,
@@ -94,8 +99,12 @@ pub struct MintReputationNFT<'info> {
     pub mint_authority: UncheckedAccount<'info>,
     
     pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
 }
 
 pub fn mint_reputation_nft(
@@ -105,8 +114,8 @@ pub fn mint_reputation_nft(
 ) -> Result<()> {
     require!(uri_len <= 100, crate::errors::ReputationError::DescriptionTooLong);
     
-    let agent = ctx.accounts.agent.key();
     let agent_profile = &ctx.accounts.agent_profile;
+    let agent = agent_profile.agent_id;
     let clock = Clock::get()?;
     
     // Determine level based on current reputation
@@ -115,6 +124,7 @@ pub fn mint_reputation_nft(
     // Create NFT account
     let nft = &mut ctx.accounts.reputation_nft;
     nft.agent = agent;
+    nft.mint = ctx.accounts.nft_mint.key();
     nft.level = level.clone();
     nft.score_at_mint = agent_profile.reputation_score;
     nft.minted_at = clock.unix_timestamp;
@@ -122,7 +132,7 @@ pub fn mint_reputation_nft(
     nft.bump = ctx.bumps.reputation_nft;
     
     // Mint the NFT (soulbound - non-transferable)
-    let seeds = &[b"mint_authority", &[ctx.bumps.mint_authority]];
+    let seeds = &[b"mint_authority".as_ref(), &[ctx.bumps.mint_authority]];
     let signer = &[&seeds[..]];
     
     token::mint_to(
@@ -138,6 +148,30 @@ pub fn mint_reputation_nft(
         1, // NFT = 1 token
     )?;
     
+    // Let the mint authority burn and close the token at deregistration, whoever holds it
+    token::approve(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Approve {
+                to: ctx.accounts.agent_token_account.to_account_info(),
+                delegate: ctx.accounts.mint_authority.to_account_info(),
+                authority: ctx.accounts.agent.to_account_info(),
+            },
+        ),
+        1,
+    )?;
+    token::set_authority(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            SetAuthority {
+                current_authority: ctx.accounts.agent.to_account_info(),
+                account_or_mint: ctx.accounts.agent_token_account.to_account_info(),
+            },
+        ),
+        AuthorityType::CloseAccount,
+        Some(ctx.accounts.mint_authority.key()),
+    )?;
+    
     // Freeze the account to make it soulbound (non-transferable)
     token::freeze_account(
         CpiContext::new_with_signer(
@@ -164,18 +198,22 @@ pub struct UpgradeReputationNFT<'info> {
     pub agent: Signer<'info>,
     
     #[account(
-        seeds = [b"agent", agent.key().as_ref()],
+        seeds = [b"agent", agent_profile.agent_id.as_ref()],
         bump = agent_profile.bump,
+        constraint = is_authorized_signer(&agent_profile, &agent.key(), session.as_deref(), SCOPE_NFT)?
     )]
     pub agent_profile: Account<'info, AgentProfile>,
     
     #[account(
         mut,
-        seeds = [b"reputation_nft", agent.key().as_ref()],
+        seeds = [b"reputation_nft", agent_profile.agent_id.as_ref()],
         bump = reputation_nft.bump,
-        constraint = reputation_nft.agent == agent.key()
+        constraint = reputation_nft.agent == agent_profile.agent_id
     )]
     pub reputation_nft: Account<'info, ReputationNFT>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
 }
 
 pub fn upgrade_reputation_nft(ctx: Context<UpgradeReputationNFT>) -> Result<()> {
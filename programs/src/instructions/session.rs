@@ -0,0 +1,135 @@
+use anchor_lang::prelude::*;
+use crate::state::AgentProfile;
+use crate::errors::ReputationError;
+
+// Scoped session keys
+// The owner grants a short-lived key a bitmask of instruction scopes so an
+// unattended agent never holds the owner key. Agent-signed instructions accept
+// the operating key, the owner, or a live session key carrying their scope.
+// Rotating the owner invalidates every session it granted.
+
+pub const SCOPE_VOUCH: u64 = 1 << 0; // vouch_for, vouch_against, vouch_weighted
+pub const SCOPE_WITHDRAW_VOUCH: u64 = 1 << 1;
+pub const SCOPE_TASKS: u64 = 1 << 2; // accept_task
+pub const SCOPE_VOTE: u64 = 1 << 3; // vote_proposal, change_vote
+pub const SCOPE_PROPOSE: u64 = 1 << 4; // create, attach to, cancel and close proposals
+pub const SCOPE_DELEGATE: u64 = 1 << 5; // delegate, undelegate
+pub const SCOPE_DISPUTE: u64 = 1 << 6; // open, respond to and vote on disputes
+pub const SCOPE_ZK_PROOF: u64 = 1 << 7;
+pub const SCOPE_NFT: u64 = 1 << 8; // mint and upgrade the reputation NFT
+pub const ALL_SCOPES: u64 = (1 << 9) - 1;
+
+pub const MAX_SESSION_DURATION: i64 = 86400 * 30;
+
+#[account]
+pub struct SessionKey {
+    pub agent_id: Pubkey,
+    pub signer: Pubkey, // The session key itself
+    pub scope: u64, // Bitmask of SCOPE_* constants
+    pub granted_by: Pubkey, // Owner at grant time; the session lapses if the owner changes
+    pub expires_at: i64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl SessionKey {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 32 + 8 + 8 + 1;
+}
+
+/// Whether `signer` may act for the agent in `scope`
+/// Used as an account constraint by every agent-signed instruction
+pub fn is_authorized_signer(
+    profile: &AgentProfile,
+    signer: &Pubkey,
+    session: Option<&SessionKey>,
+    scope: u64,
+) -> Result<bool> {
+    if *signer == profile.operating_key || *signer == profile.owner {
+        return Ok(true);
+    }
+    let Some(session) = session else {
+        return Ok(false);
+    };
+    Ok(session.agent_id == profile.agent_id
+        && session.signer == *signer
+        && session.granted_by == profile.owner
+        && session.scope & scope == scope
+        && Clock::get()?.unix_timestamp < session.expires_at)
+}
+
+#[derive(Accounts)]
+#[instruction(signer: Pubkey)]
+pub struct GrantSessionKey<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        seeds = [b"agent", agent_profile.agent_id.as_ref()],
+        bump = agent_profile.bump,
+        constraint = agent_profile.owner == owner.key()
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = SessionKey::LEN,
+        seeds = [b"session", agent_profile.agent_id.as_ref(), signer.as_ref()],
+        bump
+    )]
+    pub session: Account<'info, SessionKey>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Grant `signer` the given scopes until `expires_at` (owner only)
+pub fn grant_session_key(ctx: Context<GrantSessionKey>, signer: Pubkey, scope: u64, expires_at: i64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(scope != 0 && scope & !ALL_SCOPES == 0, ReputationError::InvalidSessionScope);
+    require!(
+        expires_at > now && expires_at <= now.saturating_add(MAX_SESSION_DURATION),
+        ReputationError::InvalidParameter
+    );
+    
+    let profile = &ctx.accounts.agent_profile;
+    let session = &mut ctx.accounts.session;
+    session.agent_id = profile.agent_id;
+    session.signer = signer;
+    session.scope = scope;
+    session.granted_by = profile.owner;
+    session.expires_at = expires_at;
+    session.created_at = now;
+    session.bump = ctx.bumps.session;
+    
+    msg!("Session key {} granted for agent {}: scope {:#x} until {}",
+        signer, profile.agent_id, scope, expires_at);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RevokeSessionKey<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        seeds = [b"agent", agent_profile.agent_id.as_ref()],
+        bump = agent_profile.bump,
+        constraint = agent_profile.owner == owner.key()
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"session", agent_profile.agent_id.as_ref(), session.signer.as_ref()],
+        bump = session.bump,
+    )]
+    pub session: Account<'info, SessionKey>,
+}
+
+/// Revoke a session key immediately, whether or not it has expired
+pub fn revoke_session_key(ctx: Context<RevokeSessionKey>) -> Result<()> {
+    msg!("Session key {} revoked for agent {}",
+        ctx.accounts.session.signer, ctx.accounts.agent_profile.agent_id);
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::state::ProtocolConfig;
+use crate::errors::ReputationError;
+use crate::instructions::governance::{GovernanceProposal, ProposalStatus, ProposalType, require_proposal_executable};
+
+/// Protocol treasury
+/// Owns a reputation-mint token account that collects vouching fees, proposal fees,
+/// forfeited challenge stakes and proposal deposits, and slashed stakes. Funds leave
+/// through governance-approved TreasurySpend proposals.
+
+#[account]
+pub struct Treasury {
+    pub token_account: Pubkey,
+    pub total_collected: u64,
+    pub total_spent: u64,
+    pub bump: u8,
+}
+
+impl Treasury {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 1;
+}
+
+/// Move tokens into the treasury and record them as collected
+pub fn deposit_to_treasury<'info>(
+    treasury: &mut Account<'info, Treasury>,
+    transfer: CpiContext<'_, '_, '_, 'info, Transfer<'info>>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    token::transfer(transfer, amount)?;
+    treasury.total_collected = treasury.total_collected.saturating_add(amount);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.authority == authority.key()
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = Treasury::LEN,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = reputation_mint,
+        associated_token::authority = treasury,
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    
+    #[account(address = config.reputation_mint)]
+    pub reputation_mint: Account<'info, Mint>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+    let treasury = &mut ctx.accounts.treasury;
+    treasury.token_account = ctx.accounts.treasury_token_account.key();
+    treasury.total_collected = 0;
+    treasury.total_spent = 0;
+    treasury.bump = ctx.bumps.treasury;
+    
+    msg!("Treasury initialized: {}", treasury.token_account);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SpendTreasury<'info> {
+    pub executor: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = proposal.proposal_type == ProposalType::TreasurySpend
+    )]
+    pub proposal: Account<'info, GovernanceProposal>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(
+        mut,
+        address = treasury.token_account
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = config.reputation_mint,
+        constraint = recipient_token_account.owner == proposal.recipient
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+/// Pay out a passed TreasurySpend proposal: new_value tokens to the proposal's recipient
+pub fn spend_treasury(ctx: Context<SpendTreasury>) -> Result<()> {
+    let proposal = &mut ctx.accounts.proposal;
+    let clock = Clock::get()?;
+    
+    require_proposal_executable(proposal, &clock)?;
+    require!(
+        ctx.accounts.treasury_token_account.amount >= proposal.new_value,
+        ReputationError::InsufficientTreasuryFunds
+    );
+    
+    let seeds = &[b"treasury".as_ref(), &[ctx.accounts.treasury.bump]];
+    let signer = &[&seeds[..]];
+    
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.treasury_token_account.to_account_info(),
+                to: ctx.accounts.recipient_token_account.to_account_info(),
+                authority: ctx.accounts.treasury.to_account_info(),
+            },
+            signer,
+        ),
+        proposal.new_value,
+    )?;
+    
+    let treasury = &mut ctx.accounts.treasury;
+    treasury.total_spent = treasury.total_spent.saturating_add(proposal.new_value);
+    proposal.status = ProposalStatus::Executed;
+    proposal.executed_at = clock.unix_timestamp;
+    
+    msg!("Treasury paid {} to {}", proposal.new_value, proposal.recipient);
+    Ok(())
+}
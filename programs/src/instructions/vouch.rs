@@ -1,7 +1,16 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, TokenAccount, Transfer};
-use crate::state::{AgentProfile, VouchRecord, ProtocolConfig};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, TokenAccount, Transfer};
+use crate::state::{AgentProfile, VouchRecord, ChallengeStatus, ProtocolConfig};
 use crate::errors::ReputationError;
+use crate::instructions::treasury::{Treasury, deposit_to_treasury};
+use crate::instructions::session::{SessionKey, is_authorized_signer, SCOPE_VOUCH, SCOPE_WITHDRAW_VOUCH};
+
+/// Stake escrow PDA seed, followed by the agent_id
+/// Every vouch, challenge and weighted vouch an agent makes is staked into its one
+/// escrow, so slashes and challenge rewards always reach the agent's whole stake.
+/// staked_amount on the profile tracks the escrow's balance.
+pub const STAKE_ESCROW_SEED: &[u8] = b"stake";
 
 #[derive(Accounts)]
 #[instruction(amount: u64)]
@@ -11,8 +20,9 @@ pub struct VouchFor<'info> {
     
     #[account(
         mut,
-        seeds = [b"agent", voucher.key().as_ref()],
+        seeds = [b"agent", voucher_profile.agent_id.as_ref()],
         bump = voucher_profile.bump,
+        constraint = is_authorized_signer(&voucher_profile, &voucher.key(), session.as_deref(), SCOPE_VOUCH)?
     )]
     pub voucher_profile: Account<'info, AgentProfile>,
     
@@ -31,7 +41,7 @@ pub struct VouchFor<'info> {
         init,
         payer = voucher,
         space = VouchRecord::LEN,
-        seeds = [b"vouch", voucher.key().as_ref(), vouched_for.key().as_ref()],
+        seeds = [b"vouch", voucher_profile.agent_id.as_ref(), vouched_for.key().as_ref()],
         bump
     )]
     pub vouch_record: Account<'info, VouchRecord>,
@@ -52,24 +62,40 @@ pub struct VouchFor<'info> {
     #[account(
         mut,
         associated_token::mint = config.reputation_mint,
-        associated_token::authority = vouch_escrow,
+        associated_token::authority = stake_escrow,
     )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    pub stake_token_account: Account<'info, TokenAccount>,
     
-    /// CHECK: PDA that owns the escrow account
+    /// CHECK: PDA that owns the voucher's stake escrow
     #[account(
-        seeds = [b"escrow", vouch_record.key().as_ref()],
+        seeds = [STAKE_ESCROW_SEED, voucher_profile.agent_id.as_ref()],
         bump,
     )]
-    pub vouch_escrow: UncheckedAccount<'info>,
+    pub stake_escrow: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(
+        mut,
+        address = treasury.token_account
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
     
     pub token_program: Program<'info, token::Token>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
 }
 
 pub fn vouch_for(ctx: Context<VouchFor>, amount: u64) -> Result<()> {
-    let voucher_key = ctx.accounts.voucher.key();
+    let voucher_key = ctx.accounts.voucher_profile.agent_id;
     let vouched_for_key = ctx.accounts.vouched_for.key();
     
     require!(voucher_key != vouched_for_key, ReputationError::SelfVouchNotAllowed);
@@ -86,6 +112,7 @@ pub fn vouch_for(ctx: Context<VouchFor>, amount: u64) -> Result<()> {
     vouch.vouched_for = vouched_for_key;
     vouch.amount = amount;
     vouch.is_positive = true;
+    vouch.challenge_status = ChallengeStatus::None;
     vouch.created_at = clock.unix_timestamp;
     vouch.bump = ctx.bumps.vouch_record;
     
@@ -94,14 +121,31 @@ pub fn vouch_for(ctx: Context<VouchFor>, amount: u64) -> Result<()> {
         ctx.accounts.vouched_for_profile.positive_vouches.saturating_add(1);
     ctx.accounts.voucher_profile.staked_amount = 
         ctx.accounts.voucher_profile.staked_amount.saturating_add(amount);
+    ctx.accounts.voucher_profile.open_vouches = 
+        ctx.accounts.voucher_profile.open_vouches.saturating_add(1);
     
-    // Transfer tokens to escrow
+    // Vouching fee on top of the stake goes to the treasury
+    let fee = ctx.accounts.config.vouch_fee(amount);
+    deposit_to_treasury(
+        &mut ctx.accounts.treasury,
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.voucher_token_account.to_account_info(),
+                to: ctx.accounts.treasury_token_account.to_account_info(),
+                authority: ctx.accounts.voucher.to_account_info(),
+            },
+        ),
+        fee,
+    )?;
+    
+    // Transfer tokens to the voucher's stake escrow
     token::transfer(
         CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
                 from: ctx.accounts.voucher_token_account.to_account_info(),
-                to: ctx.accounts.escrow_token_account.to_account_info(),
+                to: ctx.accounts.stake_token_account.to_account_info(),
                 authority: ctx.accounts.voucher.to_account_info(),
             },
         ),
@@ -121,8 +165,9 @@ pub struct VouchAgainst<'info> {
     
     #[account(
         mut,
-        seeds = [b"agent", voucher.key().as_ref()],
+        seeds = [b"agent", voucher_profile.agent_id.as_ref()],
         bump = voucher_profile.bump,
+        constraint = is_authorized_signer(&voucher_profile, &voucher.key(), session.as_deref(), SCOPE_VOUCH)?
     )]
     pub voucher_profile: Account<'info, AgentProfile>,
     
@@ -141,7 +186,7 @@ pub struct VouchAgainst<'info> {
         init,
         payer = voucher,
         space = VouchRecord::LEN,
-        seeds = [b"vouch", voucher.key().as_ref(), vouched_against.key().as_ref()],
+        seeds = [b"vouch", voucher_profile.agent_id.as_ref(), vouched_against.key().as_ref()],
         bump
     )]
     pub vouch_record: Account<'info, VouchRecord>,
@@ -152,11 +197,50 @@ pub struct VouchAgainst<'info> {
     )]
     pub config: Account<'info, ProtocolConfig>,
     
+    #[account(
+        mut,
+        associated_token::mint = config.reputation_mint,
+        associated_token::authority = voucher,
+    )]
+    pub voucher_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        associated_token::mint = config.reputation_mint,
+        associated_token::authority = stake_escrow,
+    )]
+    pub stake_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: PDA that owns the voucher's stake escrow
+    #[account(
+        seeds = [STAKE_ESCROW_SEED, voucher_profile.agent_id.as_ref()],
+        bump,
+    )]
+    pub stake_escrow: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(
+        mut,
+        address = treasury.token_account
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, token::Token>,
     pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
 }
 
 pub fn vouch_against(ctx: Context<VouchAgainst>, amount: u64) -> Result<()> {
-    let voucher_key = ctx.accounts.voucher.key();
+    let voucher_key = ctx.accounts.voucher_profile.agent_id;
     let vouched_against_key = ctx.accounts.vouched_against.key();
     
     require!(voucher_key != vouched_against_key, ReputationError::SelfVouchNotAllowed);
@@ -173,12 +257,47 @@ pub fn vouch_against(ctx: Context<VouchAgainst>, amount: u64) -> Result<()> {
     vouch.vouched_for = vouched_against_key;
     vouch.amount = amount;
     vouch.is_positive = false;
+    vouch.challenge_status = ChallengeStatus::Pending;
     vouch.created_at = clock.unix_timestamp;
     vouch.bump = ctx.bumps.vouch_record;
     
-    // Update profile
+    // Update profiles
     ctx.accounts.vouched_against_profile.negative_vouches = 
         ctx.accounts.vouched_against_profile.negative_vouches.saturating_add(1);
+    ctx.accounts.vouched_against_profile.open_disputes = 
+        ctx.accounts.vouched_against_profile.open_disputes.saturating_add(1);
+    ctx.accounts.voucher_profile.staked_amount = 
+        ctx.accounts.voucher_profile.staked_amount.saturating_add(amount);
+    ctx.accounts.voucher_profile.open_vouches = 
+        ctx.accounts.voucher_profile.open_vouches.saturating_add(1);
+    
+    // Vouching fee on top of the stake goes to the treasury
+    let fee = ctx.accounts.config.vouch_fee(amount);
+    deposit_to_treasury(
+        &mut ctx.accounts.treasury,
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.voucher_token_account.to_account_info(),
+                to: ctx.accounts.treasury_token_account.to_account_info(),
+                authority: ctx.accounts.voucher.to_account_info(),
+            },
+        ),
+        fee,
+    )?;
+    
+    // Escrow the challenge stake, same as a positive vouch
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.voucher_token_account.to_account_info(),
+                to: ctx.accounts.stake_token_account.to_account_info(),
+                authority: ctx.accounts.voucher.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
     
     msg!("Negative vouch: {} challenged {} with {} stake", 
         voucher_key, vouched_against_key, amount);
@@ -187,20 +306,24 @@ pub fn vouch_against(ctx: Context<VouchAgainst>, amount: u64) -> Result<()> {
 
 #[derive(Accounts)]
 pub struct WithdrawVouch<'info> {
-    #[account(mut)]
     pub voucher: Signer<'info>,
     
     #[account(
         mut,
-        seeds = [b"agent", voucher.key().as_ref()],
+        seeds = [b"agent", voucher_profile.agent_id.as_ref()],
         bump = voucher_profile.bump,
+        constraint = is_authorized_signer(&voucher_profile, &voucher.key(), session.as_deref(), SCOPE_WITHDRAW_VOUCH)?
     )]
     pub voucher_profile: Account<'info, AgentProfile>,
     
+    /// CHECK: Voucher's owner, receives the stake and the record's rent
+    #[account(mut, address = voucher_profile.owner)]
+    pub owner: UncheckedAccount<'info>,
+    
     #[account(
         mut,
-        close = voucher,
-        seeds = [b"vouch", voucher.key().as_ref(), vouched_for.key().as_ref()],
+        close = owner,
+        seeds = [b"vouch", voucher_profile.agent_id.as_ref(), vouched_for.key().as_ref()],
         bump = vouch_record.bump,
     )]
     pub vouch_record: Account<'info, VouchRecord>,
@@ -218,26 +341,29 @@ pub struct WithdrawVouch<'info> {
     #[account(
         mut,
         associated_token::mint = config.reputation_mint,
-        associated_token::authority = voucher,
+        associated_token::authority = owner,
     )]
     pub voucher_token_account: Account<'info, TokenAccount>,
     
     #[account(
         mut,
         associated_token::mint = config.reputation_mint,
-        associated_token::authority = vouch_escrow,
+        associated_token::authority = stake_escrow,
     )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    pub stake_token_account: Account<'info, TokenAccount>,
     
-    /// CHECK: PDA that owns the escrow account
+    /// CHECK: PDA that owns the voucher's stake escrow
     #[account(
-        seeds = [b"escrow", vouch_record.key().as_ref()],
+        seeds = [STAKE_ESCROW_SEED, voucher_profile.agent_id.as_ref()],
         bump,
     )]
-    pub vouch_escrow: UncheckedAccount<'info>,
+    pub stake_escrow: UncheckedAccount<'info>,
     
     pub token_program: Program<'info, token::Token>,
     pub system_program: Program<'info, System>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
 }
 
 pub fn withdraw_vouch(ctx: Context<WithdrawVouch>) -> Result<()> {
@@ -251,29 +377,205 @@ pub fn withdraw_vouch(ctx: Context<WithdrawVouch>) -> Result<()> {
         ReputationError::LockupNotExpired
     );
     
-    // Return staked tokens
-    if vouch.amount > 0 && vouch.is_positive {
-        let seeds = &[b"escrow", vouch.key().as_ref(), &[ctx.bumps.vouch_escrow]];
+    // Challenges can't be walked away from before they are resolved
+    require!(
+        vouch.challenge_status != ChallengeStatus::Pending,
+        ReputationError::ChallengeUnresolved
+    );
+    
+    // Return the stake, less whatever slashes took from the escrow
+    let profile = &mut ctx.accounts.voucher_profile;
+    let returned = profile.release_stake(vouch.amount);
+    profile.open_vouches = profile.open_vouches.saturating_sub(1);
+    if returned > 0 {
+        let agent_id = profile.agent_id;
+        let seeds = &[STAKE_ESCROW_SEED, agent_id.as_ref(), &[ctx.bumps.stake_escrow]];
         let signer = &[&seeds[..]];
         
         token::transfer(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.escrow_token_account.to_account_info(),
+                    from: ctx.accounts.stake_token_account.to_account_info(),
                     to: ctx.accounts.voucher_token_account.to_account_info(),
-                    authority: ctx.accounts.vouch_escrow.to_account_info(),
+                    authority: ctx.accounts.stake_escrow.to_account_info(),
                 },
                 signer,
             ),
-            vouch.amount,
+            returned,
         )?;
     }
     
-    // Update profile
-    ctx.accounts.voucher_profile.staked_amount = 
-        ctx.accounts.voucher_profile.staked_amount.saturating_sub(vouch.amount);
+    msg!("Vouch withdrawn. Returned {} tokens", returned);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ResolveChallenge<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.authority == authority.key()
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"vouch", challenger.key().as_ref(), target.key().as_ref()],
+        bump = challenge_record.bump,
+        constraint = !challenge_record.is_positive
+    )]
+    pub challenge_record: Account<'info, VouchRecord>,
+    
+    /// CHECK: Verified by the challenge record seeds
+    pub challenger: UncheckedAccount<'info>,
+    
+    /// CHECK: Verified by the challenge record seeds
+    pub target: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", challenger.key().as_ref()],
+        bump = challenger_profile.bump,
+    )]
+    pub challenger_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", target.key().as_ref()],
+        bump = target_profile.bump,
+    )]
+    pub target_profile: Account<'info, AgentProfile>,
+    
+    /// Challenger owner's token account, paid an upheld challenge's reward
+    #[account(
+        mut,
+        associated_token::mint = config.reputation_mint,
+        associated_token::authority = challenger_profile.owner,
+    )]
+    pub challenger_token_account: Account<'info, TokenAccount>,
+    
+    /// Holds the challenge stake
+    #[account(
+        mut,
+        associated_token::mint = config.reputation_mint,
+        associated_token::authority = challenger_stake_escrow,
+    )]
+    pub challenger_stake_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: PDA that owns the challenger's stake escrow
+    #[account(
+        seeds = [STAKE_ESCROW_SEED, challenger.key().as_ref()],
+        bump,
+    )]
+    pub challenger_stake_escrow: UncheckedAccount<'info>,
+    
+    /// Pays an upheld challenge; created empty if the target never staked
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = reputation_mint,
+        associated_token::authority = target_stake_escrow,
+    )]
+    pub target_stake_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: PDA that owns the target's stake escrow
+    #[account(
+        seeds = [STAKE_ESCROW_SEED, target.key().as_ref()],
+        bump,
+    )]
+    pub target_stake_escrow: UncheckedAccount<'info>,
+    
+    #[account(address = config.reputation_mint)]
+    pub reputation_mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(
+        mut,
+        address = treasury.token_account
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Resolve a pending challenge (vouch against)
+/// Upheld: challenger is paid its stake's worth from the target's stake escrow,
+/// capped at what that escrow holds
+/// Rejected: challenger's stake is forfeited to the treasury and the negative
+/// vouch no longer counts against the target
+pub fn resolve_challenge(ctx: Context<ResolveChallenge>, challenge_upheld: bool) -> Result<()> {
+    let challenge_amount = ctx.accounts.challenge_record.amount;
+    
+    require!(
+        ctx.accounts.challenge_record.challenge_status == ChallengeStatus::Pending,
+        ReputationError::ChallengeNotPending
+    );
+    ctx.accounts.target_profile.open_disputes = 
+        ctx.accounts.target_profile.open_disputes.saturating_sub(1);
+    
+    if challenge_upheld {
+        let from_stake = ctx.accounts.target_profile.release_stake(challenge_amount);
+        if from_stake > 0 {
+            let target = ctx.accounts.target.key();
+            let seeds = &[STAKE_ESCROW_SEED, target.as_ref(), &[ctx.bumps.target_stake_escrow]];
+            let signer = &[&seeds[..]];
+            
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.target_stake_token_account.to_account_info(),
+                        to: ctx.accounts.challenger_token_account.to_account_info(),
+                        authority: ctx.accounts.target_stake_escrow.to_account_info(),
+                    },
+                    signer,
+                ),
+                from_stake,
+            )?;
+        }
+        ctx.accounts.challenge_record.challenge_status = ChallengeStatus::Upheld;
+        
+        msg!("Challenge upheld: {} rewarded {} from {}'s stake", 
+            ctx.accounts.challenger.key(), from_stake, ctx.accounts.target.key());
+    } else {
+        let forfeited = ctx.accounts.challenger_profile.release_stake(challenge_amount);
+        let challenger = ctx.accounts.challenger.key();
+        let seeds = &[STAKE_ESCROW_SEED, challenger.as_ref(), &[ctx.bumps.challenger_stake_escrow]];
+        let signer = &[&seeds[..]];
+        
+        deposit_to_treasury(
+            &mut ctx.accounts.treasury,
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.challenger_stake_token_account.to_account_info(),
+                    to: ctx.accounts.treasury_token_account.to_account_info(),
+                    authority: ctx.accounts.challenger_stake_escrow.to_account_info(),
+                },
+                signer,
+            ),
+            forfeited,
+        )?;
+        
+        ctx.accounts.challenge_record.amount = 0;
+        ctx.accounts.challenge_record.challenge_status = ChallengeStatus::Rejected;
+        ctx.accounts.target_profile.negative_vouches = 
+            ctx.accounts.target_profile.negative_vouches.saturating_sub(1);
+        
+        msg!("Challenge rejected: {} lost {} stake", challenger, forfeited);
+    }
     
-    msg!("Vouch withdrawn. Returned {} tokens", vouch.amount);
     Ok(())
 }
@@ -1,7 +1,11 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, TokenAccount, Transfer};
-use crate::state::{AgentProfile, VouchRecord, ProtocolConfig};
+use crate::state::{AgentProfile, ProtocolConfig};
 use crate::errors::ReputationError;
+use crate::instructions::zk_verification::refresh_reputation_commitment;
+use crate::instructions::treasury::{Treasury, deposit_to_treasury};
+use crate::instructions::session::{SessionKey, is_authorized_signer, SCOPE_VOUCH};
+use crate::instructions::vouch::STAKE_ESCROW_SEED;
 
 /// Weighted vouching implementation based on EigenTrust algorithm
 /// Vouch impact = base_amount * (voucher_reputation / total_network_reputation)
@@ -14,8 +18,9 @@ pub struct VouchWeighted<'info> {
     
     #[account(
         mut,
-        seeds = [b"agent", voucher.key().as_ref()],
+        seeds = [b"agent", voucher_profile.agent_id.as_ref()],
         bump = voucher_profile.bump,
+        constraint = is_authorized_signer(&voucher_profile, &voucher.key(), session.as_deref(), SCOPE_VOUCH)?
     )]
     pub voucher_profile: Account<'info, AgentProfile>,
     
@@ -34,12 +39,13 @@ pub struct VouchWeighted<'info> {
         init_if_needed,
         payer = voucher,
         space = WeightedVouchRecord::LEN,
-        seeds = [b"weighted_vouch", voucher.key().as_ref(), vouched_for.key().as_ref()],
+        seeds = [b"weighted_vouch", voucher_profile.agent_id.as_ref(), vouched_for.key().as_ref()],
         bump
     )]
     pub weighted_vouch_record: Account<'info, WeightedVouchRecord>,
     
     #[account(
+        mut,
         seeds = [b"config"],
         bump = config.bump,
     )]
@@ -55,20 +61,36 @@ pub struct VouchWeighted<'info> {
     #[account(
         mut,
         associated_token::mint = config.reputation_mint,
-        associated_token::authority = vouch_escrow,
+        associated_token::authority = stake_escrow,
     )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    pub stake_token_account: Account<'info, TokenAccount>,
     
-    /// CHECK: PDA that owns the escrow account
+    /// CHECK: PDA that owns the voucher's stake escrow
     #[account(
-        seeds = [b"escrow", weighted_vouch_record.key().as_ref()],
+        seeds = [STAKE_ESCROW_SEED, voucher_profile.agent_id.as_ref()],
         bump,
     )]
-    pub vouch_escrow: UncheckedAccount<'info>,
+    pub stake_escrow: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(
+        mut,
+        address = treasury.token_account
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
     
     pub token_program: Program<'info, token::Token>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
 }
 
 /// Extended vouch record with trust weights
@@ -118,7 +140,7 @@ pub fn calculate_weighted_impact(base_amount: u64, trust_weight: u64) -> u64 {
 }
 
 pub fn vouch_weighted(ctx: Context<VouchWeighted>, amount: u64, is_positive: bool) -> Result<()> {
-    let voucher_key = ctx.accounts.voucher.key();
+    let voucher_key = ctx.accounts.voucher_profile.agent_id;
     let vouched_for_key = ctx.accounts.vouched_for.key();
     
     require!(voucher_key != vouched_for_key, ReputationError::SelfVouchNotAllowed);
@@ -141,8 +163,12 @@ pub fn vouch_weighted(ctx: Context<VouchWeighted>, amount: u64, is_positive: boo
     // Calculate actual weighted impact
     let weighted_amount = calculate_weighted_impact(amount, trust_weight);
     
-    // Update weighted vouch record
+    // Update weighted vouch record; re-vouching reuses the same record
     let vouch = &mut ctx.accounts.weighted_vouch_record;
+    if vouch.created_at == 0 {
+        ctx.accounts.voucher_profile.open_vouches = 
+            ctx.accounts.voucher_profile.open_vouches.saturating_add(1);
+    }
     vouch.voucher = voucher_key;
     vouch.vouched_for = vouched_for_key;
     vouch.base_amount = amount;
@@ -155,6 +181,7 @@ pub fn vouch_weighted(ctx: Context<VouchWeighted>, amount: u64, is_positive: boo
     vouch.bump = ctx.bumps.weighted_vouch_record;
     
     // Apply weighted impact to target's reputation
+    let before = ctx.accounts.vouched_for_profile.reputation_score;
     if is_positive {
         ctx.accounts.vouched_for_profile.reputation_score = 
             ctx.accounts.vouched_for_profile.reputation_score.saturating_add(weighted_amount);
@@ -167,25 +194,40 @@ pub fn vouch_weighted(ctx: Context<VouchWeighted>, amount: u64, is_positive: boo
         ctx.accounts.vouched_for_profile.negative_vouches = 
             ctx.accounts.vouched_for_profile.negative_vouches.saturating_add(1);
     }
+    ctx.accounts.config.track_reputation_change(before, ctx.accounts.vouched_for_profile.reputation_score);
+    refresh_reputation_commitment(&mut ctx.accounts.vouched_for_profile, ctx.accounts.config.last_snapshot_slot)?;
     
     // Update voucher's staked amount
     ctx.accounts.voucher_profile.staked_amount = 
         ctx.accounts.voucher_profile.staked_amount.saturating_add(amount);
     
-    // Transfer tokens to escrow
-    if is_positive {
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.voucher_token_account.to_account_info(),
-                    to: ctx.accounts.escrow_token_account.to_account_info(),
-                    authority: ctx.accounts.voucher.to_account_info(),
-                },
-            ),
-            amount,
-        )?;
-    }
+    // Vouching fee on top of the stake goes to the treasury
+    let fee = ctx.accounts.config.vouch_fee(amount);
+    deposit_to_treasury(
+        &mut ctx.accounts.treasury,
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.voucher_token_account.to_account_info(),
+                to: ctx.accounts.treasury_token_account.to_account_info(),
+                authority: ctx.accounts.voucher.to_account_info(),
+            },
+        ),
+        fee,
+    )?;
+    
+    // Stake goes to the stake escrow either way, so staked_amount stays backed
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.voucher_token_account.to_account_info(),
+                to: ctx.accounts.stake_token_account.to_account_info(),
+                authority: ctx.accounts.voucher.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
     
     msg!("Weighted vouch: {} -> {} | Base: {}, Weight: {}bps, Impact: {}", 
         voucher_key, vouched_for_key, amount, trust_weight, weighted_amount);
@@ -211,6 +253,7 @@ pub struct PropagateTrust<'info> {
     pub agent: UncheckedAccount<'info>,
     
     #[account(
+        mut,
         seeds = [b"config"],
         bump = config.bump,
     )]
@@ -233,7 +276,7 @@ pub fn propagate_trust(
     vouch_count: u8, // Actual number of vouches used
 ) -> Result<()> {
     let profile = &mut ctx.accounts.agent_profile;
-    let config = &ctx.accounts.config;
+    let config = &mut ctx.accounts.config;
     
     require!(vouch_count <= 10, ReputationError::InvalidParameter);
     
@@ -245,14 +288,15 @@ pub fn propagate_trust(
     let mut trust_flow: u64 = 0;
     let mut total_reputation: u64 = 0;
     
-    for i in 0..vouch_count as usize {
-        total_reputation = total_reputation.saturating_add(incoming_vouches[i].reputation);
+    let incoming = &incoming_vouches[..vouch_count as usize];
+    for vouch in incoming {
+        total_reputation = total_reputation.saturating_add(vouch.reputation);
     }
     
     if total_reputation > 0 {
-        for i in 0..vouch_count as usize {
-            let voucher_rep = incoming_vouches[i].reputation;
-            let weight = incoming_vouches[i].weight;
+        for vouch in incoming {
+            let voucher_rep = vouch.reputation;
+            let weight = vouch.weight;
             // Contribution = (voucher_rep / total_rep) * weight
             let contribution = voucher_rep
                 .saturating_mul(weight)
@@ -278,11 +322,14 @@ pub fn propagate_trust(
         let max_increase = profile.reputation_score.saturating_div(10);
         let capped_increase = increase.min(max_increase);
         
+        let before = profile.reputation_score;
         profile.reputation_score = profile.reputation_score.saturating_add(capped_increase);
+        config.track_reputation_change(before, profile.reputation_score);
         profile.last_activity_timestamp = Clock::get()?.unix_timestamp;
+        refresh_reputation_commitment(profile, config.last_snapshot_slot)?;
         
         msg!("Trust propagated: {} gained {} reputation from network", 
-            profile.owner, capped_increase);
+            profile.agent_id, capped_increase);
     }
     
     Ok(())
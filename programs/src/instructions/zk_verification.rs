@@ -1,14 +1,25 @@
 use anchor_lang::prelude::*;
-use crate::state::AgentProfile;
+use crate::state::{AgentProfile, ProtocolConfig};
+use crate::groth16;
+use crate::errors::ReputationError;
+use crate::instructions::session::{SessionKey, is_authorized_signer, SCOPE_ZK_PROOF};
 
-/// Zero-Knowledge Proof verification for privacy-preserving reputation
-/// Allows agents to prove statements about their reputation without revealing the exact score
+// Zero-Knowledge Proof verification of reputation statements
+// Lets agents prove statements about their reputation ("above N") without revealing
+// the score. Proofs are checked against the profile's reputation commitment,
+// Poseidon(reputation_score, blinding), where the blinding factor is a secret the
+// owner keeps off-chain. The owner sets the commitment with commit_reputation, which
+// proves it opens to the current score; any reputation change expires it.
 
+/// Verification key for one circuit - PDA seeded by [zk_registry, circuit]
+/// Each statement kind is checked against its own circuit's key, so a proof for
+/// one statement can never pass as another
 #[account]
 pub struct ZKVerificationKey {
     pub authority: Pubkey,
+    pub circuit: ZKCircuit,
     pub circuit_hash: [u8; 32],
-    pub verification_key: [u8; 1000], // Fixed-size Groth16 or PLONK verification key
+    pub verification_key: [u8; 1000], // Fixed-size Groth16 verification key (see groth16.rs for layout)
     pub vk_len: u16, // Actual length of VK used
     pub bump: u8,
 }
@@ -16,18 +27,24 @@ pub struct ZKVerificationKey {
 #[account]
 pub struct ZKProofRecord {
     pub prover: Pubkey,
+    pub nonce: u64, // Per-prover sequence number, used in the PDA seeds
     pub statement: ZKStatement,
     pub proof_hash: [u8; 32],
+    pub commitment: [u8; 32], // Reputation commitment the proof was checked against
     pub verified: bool,
     pub verified_at: i64,
     pub bump: u8,
 }
 
+impl ZKVerificationKey {
+    pub const LEN: usize = 8 + 32 + 1 + 32 + 1000 + 2 + 1;
+}
+
 impl ZKProofRecord {
-    pub const LEN: usize = 8 + 32 + 1 + 32 + 1 + 8 + 1;
+    pub const LEN: usize = 8 + 32 + 8 + 1 + 32 + 32 + 1 + 8 + 1;
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
 pub enum ZKStatement {
     ReputationAbove(u64),        // Prove rep > X
     ReputationBelow(u64),        // Prove rep < X
@@ -36,16 +53,53 @@ pub enum ZKStatement {
     NoNegativeVouches,           // Prove no negative vouches
 }
 
+impl ZKStatement {
+    /// Circuit that proves this statement, None for public profile facts
+    pub fn circuit(&self) -> Option<ZKCircuit> {
+        match self {
+            ZKStatement::ReputationAbove(_) => Some(ZKCircuit::Above),
+            ZKStatement::ReputationBelow(_) => Some(ZKCircuit::Below),
+            ZKStatement::ReputationInRange(_, _) => Some(ZKCircuit::InRange),
+            ZKStatement::IsActive | ZKStatement::NoNegativeVouches => None,
+        }
+    }
+}
+
+/// Groth16 circuits, one verification key registry each
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ZKCircuit {
+    Above,   // circuits/reputation_above.circom
+    Below,   // No circuit yet
+    InRange, // No circuit yet
+    Commitment, // circuits/reputation_commitment.circom, checked by commit_reputation
+}
+
+impl ZKCircuit {
+    /// Whether a circuit for this statement exists in circuits/
+    /// Keys can't be registered and proofs aren't accepted for the others
+    pub fn is_supported(&self) -> bool {
+        matches!(self, ZKCircuit::Above | ZKCircuit::Commitment)
+    }
+}
+
 #[derive(Accounts)]
+#[instruction(circuit: ZKCircuit)]
 pub struct InitializeZKRegistry<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
     
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.authority == authority.key()
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 32 + 1000 + 2 + 1, // Fixed size for VK
-        seeds = [b"zk_registry"],
+        space = ZKVerificationKey::LEN,
+        seeds = [b"zk_registry".as_ref(), &[circuit as u8]],
         bump
     )]
     pub zk_registry: Account<'info, ZKVerificationKey>,
@@ -53,22 +107,68 @@ pub struct InitializeZKRegistry<'info> {
     pub system_program: Program<'info, System>,
 }
 
+/// Register the verification key for one circuit (protocol authority)
 pub fn initialize_zk_registry(
     ctx: Context<InitializeZKRegistry>,
+    circuit: ZKCircuit,
     circuit_hash: [u8; 32],
     verification_key: [u8; 1000],
     vk_len: u16,
 ) -> Result<()> {
+    require!(circuit.is_supported(), ReputationError::UnsupportedZKStatement);
     require!(vk_len <= 1000, crate::errors::ReputationError::InvalidParameter);
+    // Groth16 key: alpha, beta, gamma, delta followed by at least one IC point
+    let ic_len = (vk_len as usize).saturating_sub(groth16::VK_HEADER_LEN);
+    require!(
+        vk_len as usize >= groth16::VK_HEADER_LEN + groth16::G1_LEN && ic_len % groth16::G1_LEN == 0,
+        crate::errors::ReputationError::InvalidParameter
+    );
     
     let registry = &mut ctx.accounts.zk_registry;
     registry.authority = ctx.accounts.authority.key();
+    registry.circuit = circuit;
     registry.circuit_hash = circuit_hash;
     registry.verification_key = verification_key;
     registry.vk_len = vk_len;
     registry.bump = ctx.bumps.zk_registry;
     
-    msg!("ZK registry initialized with circuit: {:?}", circuit_hash);
+    msg!("ZK registry initialized for {:?} with circuit: {:?}", circuit, circuit_hash);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateVerificationKey<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"zk_registry".as_ref(), &[zk_registry.circuit as u8]],
+        bump = zk_registry.bump,
+        constraint = zk_registry.authority == authority.key()
+    )]
+    pub zk_registry: Account<'info, ZKVerificationKey>,
+}
+
+/// Rotate the circuit and verification key (registry authority, normally the governance PDA)
+pub fn update_verification_key(
+    ctx: Context<UpdateVerificationKey>,
+    circuit_hash: [u8; 32],
+    verification_key: [u8; 1000],
+    vk_len: u16,
+) -> Result<()> {
+    require!(vk_len <= 1000, ReputationError::InvalidParameter);
+    let ic_len = (vk_len as usize).saturating_sub(groth16::VK_HEADER_LEN);
+    require!(
+        vk_len as usize >= groth16::VK_HEADER_LEN + groth16::G1_LEN && ic_len % groth16::G1_LEN == 0,
+        ReputationError::InvalidParameter
+    );
+    
+    let registry = &mut ctx.accounts.zk_registry;
+    registry.circuit_hash = circuit_hash;
+    registry.verification_key = verification_key;
+    registry.vk_len = vk_len;
+    
+    msg!("ZK verification key rotated, circuit: {:?}", circuit_hash);
     Ok(())
 }
 
@@ -79,16 +179,20 @@ pub struct SubmitZKProof<'info> {
     pub prover: Signer<'info>,
     
     #[account(
-        seeds = [b"agent", prover.key().as_ref()],
+        mut,
+        seeds = [b"agent", prover_profile.agent_id.as_ref()],
         bump = prover_profile.bump,
+        constraint = is_authorized_signer(&prover_profile, &prover.key(), session.as_deref(), SCOPE_ZK_PROOF)?
     )]
     pub prover_profile: Account<'info, AgentProfile>,
     
+    /// Verification key for the statement's circuit, omitted for public-fact statements
     #[account(
-        seeds = [b"zk_registry"],
+        seeds = [b"zk_registry".as_ref(), &[zk_registry.circuit as u8]],
         bump = zk_registry.bump,
+        constraint = statement.circuit() == Some(zk_registry.circuit) @ ReputationError::UnsupportedZKStatement
     )]
-    pub zk_registry: Account<'info, ZKVerificationKey>,
+    pub zk_registry: Option<Account<'info, ZKVerificationKey>>,
     
     #[account(
         init,
@@ -96,14 +200,17 @@ pub struct SubmitZKProof<'info> {
         space = ZKProofRecord::LEN,
         seeds = [
             b"zk_proof",
-            prover.key().as_ref(),
-            &Clock::get()?.unix_timestamp.to_le_bytes()
+            prover_profile.agent_id.as_ref(),
+            &prover_profile.proof_count.to_le_bytes()
         ],
         bump
     )]
     pub proof_record: Account<'info, ZKProofRecord>,
     
     pub system_program: Program<'info, System>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
 }
 
 pub fn submit_zk_proof(
@@ -116,72 +223,76 @@ pub fn submit_zk_proof(
 ) -> Result<bool> {
     require!(proof_len <= 500, crate::errors::ReputationError::InvalidParameter);
     require!(input_count <= 10, crate::errors::ReputationError::InvalidParameter);
-    let prover = ctx.accounts.prover.key();
+    let prover = ctx.accounts.prover_profile.agent_id;
     let prover_profile = &ctx.accounts.prover_profile;
-    let registry = &ctx.accounts.zk_registry;
     
-    // STUB: In production, this would:
-    // 1. Verify the ZK proof using the verification key
-    // 2. Check that public inputs match the statement
-    // 3. Verify without revealing private reputation score
-    
-    // For now, simulate verification based on actual reputation
-    // Convert fixed array to slice for processing
+    // Public inputs must describe exactly the statement being claimed
     let inputs_slice: &[u64] = &public_inputs[..input_count as usize];
-    let verified = simulate_zk_verification(
-        &statement,
-        prover_profile.reputation_score,
-        inputs_slice,
-    );
+    let verified = match statement_public_inputs(&statement) {
+        Some(expected) => {
+            // Only the statement's own circuit may vouch for it
+            let circuit = statement.circuit().ok_or(ReputationError::UnsupportedZKStatement)?;
+            require!(circuit.is_supported(), ReputationError::UnsupportedZKStatement);
+            let registry = ctx.accounts.zk_registry.as_ref()
+                .ok_or(ReputationError::UnsupportedZKStatement)?;
+            require!(
+                prover_profile.reputation_commitment != [0u8; 32],
+                ReputationError::ReputationNotCommitted
+            );
+            
+            if inputs_slice != expected.as_slice() {
+                false
+            } else {
+                // The on-chain commitment is always the last public input,
+                // binding the proof to the prover's actual reputation
+                let mut field_inputs: Vec<[u8; 32]> = inputs_slice
+                    .iter()
+                    .map(|input| groth16::field_from_u64(*input))
+                    .collect();
+                field_inputs.push(prover_profile.reputation_commitment);
+                groth16::verify(
+                    &registry.verification_key[..registry.vk_len as usize],
+                    &proof[..proof_len as usize],
+                    &field_inputs,
+                )
+            }
+        }
+        // Public facts are checked directly against the profile, no proof needed
+        None => match statement {
+            ZKStatement::IsActive => prover_profile.is_active,
+            ZKStatement::NoNegativeVouches => prover_profile.negative_vouches == 0,
+            _ => false,
+        },
+    };
     
     let clock = Clock::get()?;
     let record = &mut ctx.accounts.proof_record;
     record.prover = prover;
+    record.nonce = prover_profile.proof_count;
     record.statement = statement.clone();
-    record.proof_hash = hash_proof(&proof);
+    record.proof_hash = hash_proof(&proof[..proof_len as usize]);
+    record.commitment = prover_profile.reputation_commitment;
     record.verified = verified;
     record.verified_at = clock.unix_timestamp;
     record.bump = ctx.bumps.proof_record;
     
+    let prover_profile = &mut ctx.accounts.prover_profile;
+    prover_profile.proof_count = prover_profile.proof_count.saturating_add(1);
+    
     msg!("ZK proof submitted for {:?}: {}", statement, verified);
     
     Ok(verified)
 }
 
-/// Simulate ZK proof verification (STUB - replace with real verifier)
-fn simulate_zk_verification(
-    statement: &ZKStatement,
-    actual_reputation: u64,
-    public_inputs: &[u64],
-) -> bool {
+/// Statement public inputs the circuit is expected to expose, in order,
+/// ahead of the reputation commitment
+/// Returns None for statements about public profile fields
+fn statement_public_inputs(statement: &ZKStatement) -> Option<Vec<u64>> {
     match statement {
-        ZKStatement::ReputationAbove(threshold) => {
-            // Public input[0] should be the threshold
-            if let Some(input_threshold) = public_inputs.first() {
-                actual_reputation > *input_threshold && *input_threshold == *threshold
-            } else {
-                false
-            }
-        }
-        ZKStatement::ReputationBelow(threshold) => {
-            if let Some(input_threshold) = public_inputs.first() {
-                actual_reputation < *input_threshold && *input_threshold == *threshold
-            } else {
-                false
-            }
-        }
-        ZKStatement::ReputationInRange(min, max) => {
-            if public_inputs.len() >= 2 {
-                actual_reputation > public_inputs[0] 
-                    && actual_reputation < public_inputs[1]
-                    && public_inputs[0] == *min
-                    && public_inputs[1] == *max
-            } else {
-                false
-            }
-        }
-        ZKStatement::IsActive => true, // Would check actual active status
-        ZKStatement::NoNegativeVouches => true, // Would check vouch count
+        ZKStatement::ReputationAbove(threshold) => Some(vec![*threshold]),
+        ZKStatement::ReputationBelow(threshold) => Some(vec![*threshold]),
+        ZKStatement::ReputationInRange(min, max) => Some(vec![*min, *max]),
+        ZKStatement::IsActive | ZKStatement::NoNegativeVouches => None,
     }
 }
 
@@ -195,7 +306,7 @@ pub struct VerifyZKProof<'info> {
     pub verifier: Signer<'info>,
     
     #[account(
-        seeds = [b"zk_proof", prover.key().as_ref(), &proof_record.verified_at.to_le_bytes()],
+        seeds = [b"zk_proof", prover.key().as_ref(), &proof_record.nonce.to_le_bytes()],
         bump = proof_record.bump,
     )]
     pub proof_record: Account<'info, ZKProofRecord>,
@@ -213,13 +324,87 @@ pub fn verify_zk_proof(ctx: Context<VerifyZKProof>) -> Result<(ZKStatement, bool
     Ok((record.statement.clone(), record.verified))
 }
 
-/// Generate ZK-friendly reputation commitment
-/// In production, this would use Poseidon hash or Pedersen commitment
-pub fn generate_reputation_commitment(reputation: u64, nonce: u64) -> [u8; 32] {
-    use anchor_lang::solana_program::hash::hashv;
+#[derive(Accounts)]
+pub struct CommitReputation<'info> {
+    pub prover: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", prover_profile.agent_id.as_ref()],
+        bump = prover_profile.bump,
+        constraint = is_authorized_signer(&prover_profile, &prover.key(), session.as_deref(), SCOPE_ZK_PROOF)?
+    )]
+    pub prover_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        seeds = [b"zk_registry".as_ref(), &[ZKCircuit::Commitment as u8]],
+        bump = zk_registry.bump,
+    )]
+    pub zk_registry: Account<'info, ZKVerificationKey>,
+    
+    /// Session key account when signed by a session key
+    pub session: Option<Account<'info, SessionKey>>,
+}
+
+/// Commit to the prover's current reputation as Poseidon(reputation_score, blinding)
+/// The proof shows the commitment opens to the on-chain score without revealing the
+/// blinding factor; statement proofs are then checked against it
+pub fn commit_reputation(
+    ctx: Context<CommitReputation>,
+    commitment: [u8; 32],
+    proof: [u8; 500],
+    proof_len: u16,
+) -> Result<()> {
+    require!(proof_len <= 500, ReputationError::InvalidParameter);
+    require!(commitment != [0u8; 32], ReputationError::InvalidParameter);
+    
+    let registry = &ctx.accounts.zk_registry;
+    let profile = &mut ctx.accounts.prover_profile;
+    let public_inputs = [groth16::field_from_u64(profile.reputation_score), commitment];
+    require!(
+        groth16::verify(
+            &registry.verification_key[..registry.vk_len as usize],
+            &proof[..proof_len as usize],
+            &public_inputs,
+        ),
+        ReputationError::InvalidProof
+    );
     
-    let rep_bytes = reputation.to_le_bytes();
-    let nonce_bytes = nonce.to_le_bytes();
+    profile.reputation_commitment = commitment;
     
-    hashv(&[&rep_bytes, &nonce_bytes]).to_bytes()
+    msg!("Reputation commitment set for {}", profile.agent_id);
+    Ok(())
+}
+
+/// Checkpoint a reputation change for vote snapshots and expire the commitment to
+/// the old score; the owner re-commits with commit_reputation before proving again
+/// Must be called by every instruction that changes `reputation_score`, with the
+/// config's last snapshot slot so the checkpoint that snapshot reads is kept
+pub fn refresh_reputation_commitment(profile: &mut AgentProfile, last_snapshot_slot: u64) -> Result<()> {
+    let clock = Clock::get()?;
+    profile.record_checkpoint(clock.slot, last_snapshot_slot);
+    profile.commitment_nonce = profile.commitment_nonce.saturating_add(1);
+    profile.reputation_commitment = [0u8; 32];
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statements_map_to_their_own_circuit() {
+        assert_eq!(ZKStatement::ReputationAbove(10).circuit(), Some(ZKCircuit::Above));
+        assert_eq!(ZKStatement::ReputationBelow(10).circuit(), Some(ZKCircuit::Below));
+        assert_eq!(ZKStatement::ReputationInRange(1, 10).circuit(), Some(ZKCircuit::InRange));
+        assert_eq!(ZKStatement::IsActive.circuit(), None);
+    }
+
+    #[test]
+    fn only_circuits_that_exist_are_supported() {
+        assert!(ZKCircuit::Above.is_supported());
+        assert!(!ZKCircuit::Below.is_supported());
+        assert!(!ZKCircuit::InRange.is_supported());
+        assert!(ZKCircuit::Commitment.is_supported());
+    }
 }
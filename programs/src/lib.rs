@@ -1,13 +1,14 @@
+#![allow(clippy::manual_is_multiple_of)] // `is_multiple_of` is newer than the SBF toolchain
+
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Mint, Transfer};
 
 mod state;
 mod instructions;
 mod errors;
+mod groth16;
 
 use state::*;
 use instructions::*;
-use errors::*;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
@@ -21,18 +22,136 @@ pub mod agentreputation_dao {
     }
 
     /// Register a new agent in the reputation system
-    pub fn register_agent(ctx: Context<RegisterAgent>, agent_name_bytes: [u8; 50], name_len: u8) -> Result<()> {
+    pub fn register_agent(
+        ctx: Context<RegisterAgent>,
+        agent_name_bytes: [u8; 50],
+        name_len: u8,
+    ) -> Result<()> {
         instructions::register_agent(ctx, agent_name_bytes, name_len)
     }
 
-    /// Complete a task and earn reputation
-    pub fn complete_task(
-        ctx: Context<CompleteTask>,
+    /// Update an agent's metadata (owner only)
+    pub fn update_agent_profile(ctx: Context<UpdateAgentProfile>, metadata: AgentMetadata) -> Result<()> {
+        instructions::update_agent_profile(ctx, metadata)
+    }
+
+    /// Rotate an agent's owner and operating keys (owner only)
+    pub fn rotate_agent_keys(ctx: Context<RotateAgentKeys>, new_owner: Pubkey, new_operating_key: Pubkey) -> Result<()> {
+        instructions::rotate_agent_keys(ctx, new_owner, new_operating_key)
+    }
+
+    /// Claim a unique name for an agent without one (owner only)
+    pub fn claim_agent_name(ctx: Context<ClaimAgentName>, name: [u8; 50], name_len: u8) -> Result<()> {
+        instructions::claim_agent_name(ctx, name, name_len)
+    }
+
+    /// Release an agent's name (owner only)
+    pub fn release_agent_name(ctx: Context<ReleaseAgentName>) -> Result<()> {
+        instructions::release_agent_name(ctx)
+    }
+
+    /// Transfer an agent's name to another agent (both owners)
+    pub fn transfer_agent_name(ctx: Context<TransferAgentName>) -> Result<()> {
+        instructions::transfer_agent_name(ctx)
+    }
+
+    /// Look up the agent holding a name
+    pub fn resolve_agent_name(ctx: Context<ResolveAgentName>, name: [u8; 50], name_len: u8) -> Result<Pubkey> {
+        instructions::resolve_agent_name(ctx, name, name_len)
+    }
+
+    /// Create an organization, optionally sharing its members' slashing liability
+    pub fn create_organization(
+        ctx: Context<CreateOrganization>,
+        name: [u8; 50],
+        name_len: u8,
+        liability_bps: u64,
+    ) -> Result<()> {
+        instructions::create_organization(ctx, name, name_len, liability_bps)
+    }
+
+    /// Add an agent to an organization (org authority and agent owner)
+    pub fn join_organization(ctx: Context<JoinOrganization>) -> Result<()> {
+        instructions::join_organization(ctx)
+    }
+
+    /// Remove an agent from its organization (org authority or agent owner)
+    pub fn leave_organization(ctx: Context<LeaveOrganization>) -> Result<()> {
+        instructions::leave_organization(ctx)
+    }
+
+    /// Refresh a member's figures in its organization's aggregates
+    pub fn sync_org_member(ctx: Context<SyncOrgMember>) -> Result<()> {
+        instructions::sync_org_member(ctx)
+    }
+
+    /// Add to an organization's liability stake (org authority)
+    pub fn fund_org_stake(ctx: Context<FundOrgStake>, amount: u64) -> Result<()> {
+        instructions::fund_org_stake(ctx, amount)
+    }
+
+    /// Withdraw an organization's stake once it has no members (org authority)
+    pub fn withdraw_org_stake(ctx: Context<WithdrawOrgStake>) -> Result<()> {
+        instructions::withdraw_org_stake(ctx)
+    }
+
+    /// Check whether an agent belongs to a reputable organization
+    pub fn verify_org_membership(
+        ctx: Context<VerifyOrgMembership>,
+        min_org_reputation: u64,
+        max_slash_count: u64,
+    ) -> Result<bool> {
+        instructions::verify_org_membership(ctx, min_org_reputation, max_slash_count)
+    }
+
+    /// Deregister an agent, settling its vouch stakes and leaving a tombstone (owner only)
+    pub fn deregister_agent<'info>(ctx: Context<'_, '_, '_, 'info, DeregisterAgent<'info>>) -> Result<()> {
+        instructions::deregister_agent(ctx)
+    }
+
+    /// Grant a scoped, expiring session key (owner only)
+    pub fn grant_session_key(ctx: Context<GrantSessionKey>, signer: Pubkey, scope: u64, expires_at: i64) -> Result<()> {
+        instructions::grant_session_key(ctx, signer, scope, expires_at)
+    }
+
+    /// Revoke a session key (owner only)
+    pub fn revoke_session_key(ctx: Context<RevokeSessionKey>) -> Result<()> {
+        instructions::revoke_session_key(ctx)
+    }
+
+    /// Approve a task issuer with a reputation allowance (authority only)
+    pub fn register_task_issuer(ctx: Context<RegisterTaskIssuer>, reputation_allowance: u64) -> Result<()> {
+        instructions::register_task_issuer(ctx, reputation_allowance)
+    }
+
+    /// Add a reviewer who may approve the issuer's tasks
+    pub fn add_task_reviewer(ctx: Context<AddTaskReviewer>, reviewer: Pubkey) -> Result<()> {
+        instructions::add_task_reviewer(ctx, reviewer)
+    }
+
+    /// Create a task with a reputation budget
+    pub fn create_task(
+        ctx: Context<CreateTask>,
         task_id: [u8; 100],
         task_id_len: u8,
-        reputation_amount: u64,
+        reputation_budget: u64,
     ) -> Result<()> {
-        instructions::complete_task(ctx, task_id, task_id_len, reputation_amount)
+        instructions::create_task(ctx, task_id, task_id_len, reputation_budget)
+    }
+
+    /// Accept an open task as the working agent
+    pub fn accept_task(ctx: Context<AcceptTask>) -> Result<()> {
+        instructions::accept_task(ctx)
+    }
+
+    /// Cancel an unaccepted task and release its budget (issuer only)
+    pub fn cancel_task(ctx: Context<CancelTask>) -> Result<()> {
+        instructions::cancel_task(ctx)
+    }
+
+    /// Approve task completion and award reputation (issuer or reviewer)
+    pub fn complete_task(ctx: Context<CompleteTask>, reputation_amount: u64) -> Result<()> {
+        instructions::complete_task(ctx, reputation_amount)
     }
 
     /// Vouch for another agent (stake tokens)
@@ -45,6 +164,11 @@ pub mod agentreputation_dao {
         instructions::vouch_against(ctx, amount)
     }
 
+    /// Resolve a pending challenge, rewarding or slashing the challenger
+    pub fn resolve_challenge(ctx: Context<ResolveChallenge>, challenge_upheld: bool) -> Result<()> {
+        instructions::resolve_challenge(ctx, challenge_upheld)
+    }
+
     /// Withdraw vouch (unstake)
     pub fn withdraw_vouch(ctx: Context<WithdrawVouch>) -> Result<()> {
         instructions::withdraw_vouch(ctx)
@@ -83,25 +207,136 @@ pub mod agentreputation_dao {
         ctx: Context<CreateProposal>,
         proposal_type: ProposalType,
         new_value: u64,
+        recipient: Pubkey,
+        voting_mode: VotingMode,
         description: [u8; 200],
         desc_len: u8,
     ) -> Result<()> {
-        instructions::create_proposal(ctx, proposal_type, new_value, description, desc_len)
+        instructions::create_proposal(ctx, proposal_type, new_value, recipient, voting_mode, description, desc_len)
+    }
+
+    /// Vote on governance proposal, casting any delegated weight passed in remaining accounts
+    pub fn vote_proposal<'info>(ctx: Context<'_, '_, '_, 'info, VoteOnProposal<'info>>, choice: VoteChoice) -> Result<()> {
+        instructions::vote_proposal(ctx, choice)
     }
 
-    /// Vote on governance proposal
-    pub fn vote_proposal(ctx: Context<VoteOnProposal>, is_for: bool) -> Result<()> {
-        instructions::vote_proposal(ctx, is_for)
+    /// Cast a further batch of delegated weight after the delegate's own vote
+    pub fn cast_delegated_votes<'info>(ctx: Context<'_, '_, '_, 'info, CastDelegatedVotes<'info>>) -> Result<()> {
+        instructions::cast_delegated_votes(ctx)
+    }
+
+    /// Change a cast vote while voting is open
+    pub fn change_vote<'info>(ctx: Context<'_, '_, '_, 'info, ChangeVote<'info>>, choice: VoteChoice) -> Result<()> {
+        instructions::change_vote(ctx, choice)
+    }
+
+    /// Delegate governance vote weight to another key
+    pub fn delegate(ctx: Context<Delegate>, delegate: Pubkey) -> Result<()> {
+        instructions::delegate(ctx, delegate)
+    }
+
+    /// Revoke a vote delegation
+    pub fn undelegate(ctx: Context<Undelegate>) -> Result<()> {
+        instructions::undelegate(ctx)
     }
 
     /// Execute passed proposal
-    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+    pub fn execute_proposal<'info>(ctx: Context<'_, '_, '_, 'info, ExecuteProposal<'info>>) -> Result<()> {
         instructions::execute_proposal(ctx)
     }
 
-    /// Slash malicious agent
-    pub fn slash_agent(ctx: Context<SlashAgent>, evidence_hash: [u8; 32]) -> Result<()> {
-        instructions::slash_agent(ctx, evidence_hash)
+    /// Record a proposal's vote outcome or expiry
+    pub fn finalize_proposal(ctx: Context<FinalizeProposal>) -> Result<()> {
+        instructions::finalize_proposal(ctx)
+    }
+
+    /// Cancel an active proposal (proposer only)
+    pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+        instructions::cancel_proposal(ctx)
+    }
+
+    /// Refund or forfeit a final proposal's deposit
+    pub fn settle_proposal_deposit(ctx: Context<SettleProposalDeposit>) -> Result<()> {
+        instructions::settle_proposal_deposit(ctx)
+    }
+
+    /// Close a finished proposal to reclaim rent
+    pub fn close_proposal(ctx: Context<CloseProposal>) -> Result<()> {
+        instructions::close_proposal(ctx)
+    }
+
+    /// Close a vote record on a finished proposal
+    pub fn close_vote_record(ctx: Context<CloseVoteRecord>) -> Result<()> {
+        instructions::close_vote_record(ctx)
+    }
+
+    /// Queue a succeeded proposal behind the timelock
+    pub fn queue_proposal(ctx: Context<QueueProposal>) -> Result<()> {
+        instructions::queue_proposal(ctx)
+    }
+
+    /// Guardian veto of a queued proposal
+    pub fn veto_proposal(ctx: Context<VetoProposal>) -> Result<()> {
+        instructions::veto_proposal(ctx)
+    }
+
+    /// Replace the guardian set (authority only)
+    pub fn set_guardians(ctx: Context<SetGuardians>, guardians: Vec<Pubkey>) -> Result<()> {
+        instructions::set_guardians(ctx, guardians)
+    }
+
+    /// Attach the instruction an ExecuteInstruction proposal will invoke
+    pub fn attach_proposal_instruction(
+        ctx: Context<AttachProposalInstruction>,
+        program_id: Pubkey,
+        accounts: Vec<ProposalAccountMeta>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        instructions::attach_proposal_instruction(ctx, program_id, accounts, data)
+    }
+
+    /// Attach the parameter changes a Batch proposal will apply
+    pub fn attach_proposal_batch(ctx: Context<AttachProposalBatch>, changes: Vec<ParameterChange>) -> Result<()> {
+        instructions::attach_proposal_batch(ctx, changes)
+    }
+
+    /// Hand config, oracle and ZK registry authority to the governance PDA
+    pub fn transfer_admin_to_governance(ctx: Context<TransferAdminToGovernance>) -> Result<()> {
+        instructions::transfer_admin_to_governance(ctx)
+    }
+
+    /// Create the protocol treasury and its token account (authority only)
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+        instructions::initialize_treasury(ctx)
+    }
+
+    /// Pay out a passed TreasurySpend proposal
+    pub fn spend_treasury(ctx: Context<SpendTreasury>) -> Result<()> {
+        instructions::spend_treasury(ctx)
+    }
+
+    /// Open a slashing dispute with categorised evidence and a bond
+    pub fn open_dispute(
+        ctx: Context<OpenDispute>,
+        evidence_hash: [u8; 32],
+        category: EvidenceCategory,
+    ) -> Result<()> {
+        instructions::open_dispute(ctx, evidence_hash, category)
+    }
+
+    /// Respond to a dispute with counter-evidence and a matching bond
+    pub fn respond_to_dispute(ctx: Context<RespondToDispute>, response_hash: [u8; 32]) -> Result<()> {
+        instructions::respond_to_dispute(ctx, response_hash)
+    }
+
+    /// Reputation-weighted juror vote on a dispute
+    pub fn vote_dispute(ctx: Context<VoteDispute>, uphold: bool) -> Result<()> {
+        instructions::vote_dispute(ctx, uphold)
+    }
+
+    /// Execute an upheld dispute (slash) or dismiss it
+    pub fn slash_agent(ctx: Context<SlashAgent>) -> Result<()> {
+        instructions::slash_agent(ctx)
     }
 
     // Oracle integration functions
@@ -142,11 +377,30 @@ pub mod agentreputation_dao {
     // ZK Proof functions
     pub fn initialize_zk_registry(
         ctx: Context<InitializeZKRegistry>,
+        circuit: ZKCircuit,
+        circuit_hash: [u8; 32],
+        verification_key: [u8; 1000],
+        vk_len: u16,
+    ) -> Result<()> {
+        instructions::initialize_zk_registry(ctx, circuit, circuit_hash, verification_key, vk_len)
+    }
+
+    pub fn update_verification_key(
+        ctx: Context<UpdateVerificationKey>,
         circuit_hash: [u8; 32],
         verification_key: [u8; 1000],
         vk_len: u16,
     ) -> Result<()> {
-        instructions::initialize_zk_registry(ctx, circuit_hash, verification_key, vk_len)
+        instructions::update_verification_key(ctx, circuit_hash, verification_key, vk_len)
+    }
+
+    pub fn commit_reputation(
+        ctx: Context<CommitReputation>,
+        commitment: [u8; 32],
+        proof: [u8; 500],
+        proof_len: u16,
+    ) -> Result<()> {
+        instructions::commit_reputation(ctx, commitment, proof, proof_len)
     }
 
     pub fn submit_zk_proof(
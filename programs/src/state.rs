@@ -8,32 +8,227 @@ pub struct ProtocolConfig {
     pub min_reputation_for_vouching: u64,
     pub decay_rate_per_day: u64, // basis points (10000 = 100%)
     pub vouch_lockup_period: i64, // seconds
-    pub slash_threshold: u64, // basis points for slashing
+    pub slash_threshold: u64, // basis points for slashing (base rate, Misconduct)
     pub max_trust_multiplier: u64, // basis points (30000 = 3x)
+    pub task_fraud_slash_bps: u64, // basis points for TaskFraud
+    pub collusion_slash_bps: u64, // basis points for Collusion
+    pub malicious_slash_bps: u64, // basis points for Malicious
+    pub vouch_fee_bps: u64, // basis points of each vouch paid to the treasury
+    pub proposal_fee: u64, // reputation tokens paid to the treasury per proposal
+    pub proposal_deposit: u64, // reputation tokens escrowed per proposal, refunded on quorum
+    pub voting_period: i64, // seconds
+    pub quorum_votes: u64, // absolute quorum in reputation
+    pub quorum_bps: u64, // quorum as basis points of total_reputation (0 = off)
+    pub majority_bps: u64, // share of votes cast needed to pass (5000 = simple majority)
+    pub min_proposer_reputation: u64,
+    pub min_voter_reputation: u64,
+    pub total_reputation: u64, // Sum of all agents' reputation_score
+    pub timelock_delay: i64, // seconds between queueing and execution
+    pub guardians: [Pubkey; 5], // May veto queued proposals
+    pub guardian_count: u8,
+    pub allowed_voting_modes: u8, // Bitmask of VotingMode::bit() proposals may use
+    pub proposal_count: u64, // Id of the next proposal, used in its PDA seeds
+    pub last_snapshot_slot: u64, // Newest proposal or dispute snapshot; agents keep the checkpoint it reads
     pub bump: u8,
 }
 
 impl ProtocolConfig {
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1;
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + (5 * 32) + 1 + 1 + 8
+        + 8 + 1;
+
+    /// Governance bounds for any slash rate
+    pub const MIN_SLASH_BPS: u64 = 100; // 1%
+    pub const MAX_SLASH_BPS: u64 = 5000; // 50%
+    pub const MAX_VOUCH_FEE_BPS: u64 = 1000; // 10%
+
+    /// Governance bounds for vouching and reputation upkeep
+    pub const MAX_MIN_REPUTATION_FOR_VOUCHING: u64 = 100_000;
+    pub const MAX_DECAY_RATE_BPS: u64 = 1000; // 10% daily
+    pub const MAX_VOUCH_LOCKUP_PERIOD: i64 = 86400 * 90; // 90 days
+    pub const MIN_TRUST_MULTIPLIER: u64 = 10000; // 1x
+    pub const MAX_TRUST_MULTIPLIER: u64 = 50000; // 5x
+
+    /// Governance bounds for proposal costs, in reputation tokens
+    pub const MAX_PROPOSAL_FEE: u64 = 10_000;
+    pub const MAX_PROPOSAL_DEPOSIT: u64 = 100_000;
+
+    /// Governance bounds for the voting policy
+    pub const MIN_VOTING_PERIOD: i64 = 86400; // 1 day
+    pub const MAX_VOTING_PERIOD: i64 = 86400 * 14; // 14 days
+    pub const MAX_QUORUM_VOTES: u64 = 100_000_000;
+    pub const MAX_QUORUM_BPS: u64 = 5000; // 50% of network reputation
+    pub const MIN_MAJORITY_BPS: u64 = 5000; // simple majority
+    pub const MAX_MAJORITY_BPS: u64 = 9000; // 90%
+    pub const MIN_TIMELOCK_DELAY: i64 = 86400; // 1 day
+    pub const MAX_TIMELOCK_DELAY: i64 = 86400 * 14; // 14 days
+    pub const MAX_MIN_PROPOSER_REPUTATION: u64 = 1_000_000;
+    pub const MAX_GUARDIANS: usize = 5;
+    pub const ALL_VOTING_MODES: u8 = 0b111; // Linear | Quadratic | Conviction
+
+    /// Configured slash rate for an offence category
+    pub fn slash_rate(&self, category: EvidenceCategory) -> u64 {
+        match category {
+            EvidenceCategory::Misconduct => self.slash_threshold,
+            EvidenceCategory::TaskFraud => self.task_fraud_slash_bps,
+            EvidenceCategory::Collusion => self.collusion_slash_bps,
+            EvidenceCategory::Malicious => self.malicious_slash_bps,
+        }
+    }
+
+    /// Votes a proposal created now must reach: the larger of the absolute
+    /// and the network-relative quorum
+    pub fn quorum(&self) -> u64 {
+        let relative = (self.total_reputation as u128)
+            .saturating_mul(self.quorum_bps as u128)
+            .saturating_div(10000) as u64;
+        self.quorum_votes.max(relative)
+    }
+
+    pub fn is_guardian(&self, key: &Pubkey) -> bool {
+        self.guardians[..self.guardian_count as usize].contains(key)
+    }
+
+    /// Note a vote snapshot at `slot`, so the next reputation change of every agent
+    /// keeps the checkpoint the snapshot reads instead of overwriting it
+    pub fn take_snapshot(&mut self, slot: u64) {
+        self.last_snapshot_slot = self.last_snapshot_slot.max(slot);
+    }
+
+    /// Keep total_reputation in step with one agent's score change
+    pub fn track_reputation_change(&mut self, before: u64, after: u64) {
+        self.total_reputation = self.total_reputation.saturating_add(after).saturating_sub(before);
+    }
+
+    /// Treasury fee charged on top of a vouch stake
+    pub fn vouch_fee(&self, amount: u64) -> u64 {
+        amount.saturating_mul(self.vouch_fee_bps).saturating_div(10000)
+    }
 }
 
-/// Agent profile - PDA seeded by [agent, agent_pubkey]
+/// Offence severity declared with slashing evidence
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum EvidenceCategory {
+    Misconduct, // Minor policy violations
+    TaskFraud,  // Faked or plagiarised task results
+    Collusion,  // Vouch rings, sybil farming
+    Malicious,  // Deliberate harm to users or the protocol
+}
+
+/// Agent profile - PDA seeded by [agent, agent_id]
+/// agent_id is chosen at registration and never changes; the owner and operating
+/// keys can be rotated without moving reputation, vouches or NFTs
 #[account]
 pub struct AgentProfile {
-    pub owner: Pubkey,
-    pub name: [u8; 50], // fixed size for Anchor
+    pub agent_id: Pubkey, // Durable identity, used in every agent-keyed seed
+    pub owner: Pubkey, // Authority: rotates keys and edits the profile
+    pub operating_key: Pubkey, // Signs day-to-day actions (vouching, voting, tasks, proofs)
+    pub name: [u8; 50], // Display name, zero-padded; its normalized form owns an AgentName PDA
+    pub name_len: u8, // 0 if the agent holds no name
     pub reputation_score: u64,
     pub total_tasks_completed: u64,
     pub last_activity_timestamp: i64,
     pub is_active: bool,
     pub positive_vouches: u64,
     pub negative_vouches: u64,
-    pub staked_amount: u64,
+    pub staked_amount: u64, // Balance of the agent's stake escrow, backing all its vouches
+    pub reputation_commitment: [u8; 32], // Poseidon(reputation_score, blinding), zero until re-committed after a change
+    pub commitment_nonce: u64, // Bumped on every reputation change
+    pub checkpoints: [ReputationCheckpoint; 16], // Reputation history, oldest first
+    pub checkpoint_count: u8,
+    pub attestation_count: u64, // Nonce of the next oracle attestation for this agent
+    pub proof_count: u64, // Nonce of the next ZK proof record for this agent
+    pub dispute_count: u64, // Nonce of the next dispute against this agent
+    pub open_vouches: u32, // Vouch and weighted vouch records this agent holds as voucher
+    pub open_disputes: u32, // Unresolved disputes and pending challenges it is party to
+    pub open_proposals: u32, // Proposals it created whose deposit isn't settled yet
+    pub organization: Pubkey, // Organization it belongs to, default if none
     pub bump: u8,
+    pub metadata: AgentMetadata, // Variable length, kept last; resized by update_agent_profile
 }
 
 impl AgentProfile {
-    pub const LEN: usize = 8 + 32 + 50 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 1;
+    pub const MAX_CHECKPOINTS: usize = 16;
+    /// Size with empty metadata strings
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 50 + 1 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 32 + 8 + (Self::MAX_CHECKPOINTS * 16) + 1 + 8 + 8 + 8 + 4 + 4 + 4 + 32 + 1
+        + AgentMetadata::EMPTY_LEN;
+    
+    /// Account size needed to hold `metadata`
+    pub fn space(metadata: &AgentMetadata) -> usize {
+        Self::LEN + metadata.service_endpoint.len() + metadata.framework.len() + metadata.version.len()
+    }
+
+    /// Take `amount` of a vouch's stake back out of the pooled stake escrow
+    /// Capped at what slashes left, so releasing every vouch pays out exactly the escrow
+    pub fn release_stake(&mut self, amount: u64) -> u64 {
+        let released = amount.min(self.staked_amount);
+        self.staked_amount -= released;
+        released
+    }
+
+    /// Remove `slash_bps` of the whole stake escrow, returning the tokens taken
+    pub fn slash_stake(&mut self, slash_bps: u64) -> u64 {
+        let slashed = ((self.staked_amount as u128) * (slash_bps.min(10000) as u128) / 10000) as u64;
+        self.staked_amount -= slashed;
+        slashed
+    }
+
+    /// Record the current reputation as of `slot`
+    /// The newest checkpoint is overwritten unless a snapshot was taken since, i.e.
+    /// one reads it, so every checkpoint kept answers at least one snapshot. When
+    /// the history is full, the oldest is dropped: losing a snapshot's checkpoint
+    /// takes MAX_CHECKPOINTS changes to this agent, each after another snapshot
+    pub fn record_checkpoint(&mut self, slot: u64, last_snapshot_slot: u64) {
+        let checkpoint = ReputationCheckpoint { slot, score: self.reputation_score };
+        let count = self.checkpoint_count as usize;
+        
+        if count > 0 && self.checkpoints[count - 1].slot >= last_snapshot_slot {
+            self.checkpoints[count - 1] = checkpoint;
+            return;
+        }
+        if count == Self::MAX_CHECKPOINTS {
+            self.checkpoints.copy_within(1..count, 0);
+            self.checkpoints[count - 1] = checkpoint;
+            return;
+        }
+        self.checkpoints[count] = checkpoint;
+        self.checkpoint_count += 1;
+    }
+
+    /// Reputation as it stood before `snapshot_slot`
+    /// None if the agent didn't exist yet or the snapshot's checkpoint has since
+    /// been dropped
+    pub fn reputation_at(&self, snapshot_slot: u64) -> Option<u64> {
+        self.checkpoints[..self.checkpoint_count as usize]
+            .iter()
+            .rev()
+            .find(|checkpoint| checkpoint.slot < snapshot_slot)
+            .map(|checkpoint| checkpoint.score)
+    }
+}
+
+/// Self-description of an agent for registry consumers
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct AgentMetadata {
+    pub service_endpoint: String, // URI the agent is reachable at
+    pub capabilities: u64, // Bitmap of advertised capabilities
+    pub framework: String, // Model or framework identifier
+    pub version: String,
+    pub metadata_hash: [u8; 32], // Hash of the full off-chain metadata document
+    pub updated_at: i64,
+}
+
+impl AgentMetadata {
+    pub const MAX_ENDPOINT_LEN: usize = 200;
+    pub const MAX_FRAMEWORK_LEN: usize = 64;
+    pub const MAX_VERSION_LEN: usize = 32;
+    pub const EMPTY_LEN: usize = 4 + 8 + 4 + 4 + 32 + 8;
+}
+
+/// Reputation score after the last change in `slot`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct ReputationCheckpoint {
+    pub slot: u64,
+    pub score: u64,
 }
 
 /// Vouch record - PDA seeded by [vouch, voucher, vouched_for]
@@ -41,28 +236,76 @@ impl AgentProfile {
 pub struct VouchRecord {
     pub voucher: Pubkey,
     pub vouched_for: Pubkey,
-    pub amount: u64,
+    pub amount: u64, // Stake committed from the voucher's stake escrow
     pub is_positive: bool, // true = vouch for, false = vouch against
+    pub challenge_status: ChallengeStatus, // Only meaningful for negative vouches
     pub created_at: i64,
     pub bump: u8,
 }
 
 impl VouchRecord {
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 1 + 8 + 1;
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1 + 1 + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengeStatus {
+    None,     // Positive vouch, nothing to resolve
+    Pending,  // Challenge stake escrowed, awaiting resolution
+    Upheld,   // Challenger rewarded from the target's stake
+    Rejected, // Challenger's stake slashed
+}
+
+/// Task issuer - PDA seeded by [task_issuer, issuer_pubkey]
+/// Approved by the protocol authority with a bounded reputation allowance
+#[account]
+pub struct TaskIssuer {
+    pub authority: Pubkey,
+    pub reputation_allowance: u64, // Remaining reputation this issuer may award
+    pub tasks_created: u64,
+    pub reviewers: [Pubkey; 5], // Fixed 5 reviewer slots
+    pub reviewer_count: u8,
+    pub is_active: bool,
+    pub bump: u8,
+}
+
+impl TaskIssuer {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + (5 * 32) + 1 + 1 + 1;
+
+    /// Issuer authority or one of its approved reviewers
+    pub fn can_approve(&self, approver: &Pubkey) -> bool {
+        self.authority == *approver
+            || self.reviewers[..self.reviewer_count as usize].contains(approver)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TaskStatus {
+    Open,      // Created by issuer, waiting for an agent
+    Accepted,  // Agent assigned, waiting for approval
+    Completed, // Approved and reputation minted
 }
 
-/// Task completion record
+/// Task record - PDA seeded by [task, issuer, task_id]
 #[account]
 pub struct TaskRecord {
-    pub agent: Pubkey,
+    pub issuer: Pubkey,
+    pub agent: Pubkey, // Default until accepted
     pub task_id: [u8; 100], // fixed size for Anchor
+    pub task_id_len: u8,
+    pub reputation_budget: u64, // Max reputation the issuer will award
     pub reputation_earned: u64,
+    pub status: TaskStatus,
+    pub approved_by: Pubkey,
+    pub created_at: i64,
+    pub accepted_at: i64,
     pub completed_at: i64,
     pub bump: u8,
 }
 
 impl TaskRecord {
-    pub const LEN: usize = 8 + 32 + 100 + 8 + 8 + 1;
+    pub const LEN: usize = 8 + 32 + 32 + 100 + 1 + 8 + 8 + 1 + 32 + 8 + 8 + 8 + 1;
+    /// The id is a PDA seed, and seeds are capped at 32 bytes
+    pub const MAX_TASK_ID_LEN: usize = 32;
 }
 
 /// Reputation calculation helpers
@@ -81,12 +324,90 @@ pub fn calculate_decay(current_reputation: u64, days_inactive: i64, decay_rate:
     remaining
 }
 
-pub fn calculate_vouch_bonus(positive_vouches: u64, negative_vouches: u64) -> i64 {
-    let total = positive_vouches.saturating_add(negative_vouches);
-    if total == 0 {
-        return 0;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_profile() -> AgentProfile {
+        AgentProfile::deserialize(&mut &[0u8; AgentProfile::LEN][..]).unwrap()
+    }
+
+    #[test]
+    fn slash_takes_share_of_whole_stake() {
+        let mut profile = empty_profile();
+        profile.staked_amount = 1_000;
+        assert_eq!(profile.slash_stake(2_500), 250);
+        assert_eq!(profile.staked_amount, 750);
+        assert_eq!(profile.slash_stake(20_000), 750);
+        assert_eq!(profile.staked_amount, 0);
+    }
+
+    fn change(profile: &mut AgentProfile, slot: u64, score: u64, last_snapshot_slot: u64) {
+        profile.reputation_score = score;
+        profile.record_checkpoint(slot, last_snapshot_slot);
+    }
+
+    #[test]
+    fn flooding_changes_keeps_a_snapshot_readable() {
+        let mut profile = empty_profile();
+        change(&mut profile, 1, 500, 0);
+        for slot in 3..200 {
+            change(&mut profile, slot, slot, 2);
+        }
+        assert_eq!(profile.reputation_at(2), Some(500));
+        assert_eq!(profile.reputation_at(200), Some(199));
+        assert_eq!(profile.checkpoint_count, 2);
+    }
+
+    #[test]
+    fn changes_without_a_snapshot_in_between_share_a_checkpoint() {
+        let mut profile = empty_profile();
+        change(&mut profile, 1, 500, 0);
+        change(&mut profile, 3, 600, 2);
+        change(&mut profile, 4, 700, 2);
+        change(&mut profile, 5, 800, 5);
+        assert_eq!(profile.checkpoint_count, 3);
+        assert_eq!(profile.reputation_at(2), Some(500));
+        // The snapshot in slot 5 reads the change before it, not the one in slot 5
+        assert_eq!(profile.reputation_at(5), Some(700));
+        assert_eq!(profile.reputation_at(6), Some(800));
+    }
+
+    #[test]
+    fn a_full_history_keeps_the_newest_snapshots() {
+        let mut profile = empty_profile();
+        // A snapshot between every pair of changes
+        for i in 0..AgentProfile::MAX_CHECKPOINTS as u64 + 4 {
+            change(&mut profile, 10 * i + 1, 1000 + i, 10 * i);
+        }
+        assert_eq!(profile.checkpoint_count as usize, AgentProfile::MAX_CHECKPOINTS);
+        for i in 5..AgentProfile::MAX_CHECKPOINTS as u64 + 4 {
+            assert_eq!(profile.reputation_at(10 * i), Some(1000 + i - 1));
+        }
+        // Only snapshots older than the oldest kept checkpoint are lost
+        assert_eq!(profile.reputation_at(40), None);
+    }
+
+    #[test]
+    fn snapshots_only_move_forward() {
+        let mut config = ProtocolConfig::deserialize(&mut &[0u8; ProtocolConfig::LEN][..]).unwrap();
+        config.take_snapshot(7);
+        config.take_snapshot(5);
+        assert_eq!(config.last_snapshot_slot, 7);
+        // Any number of votes can be open at once
+        for slot in 8..100 {
+            config.take_snapshot(slot);
+        }
+        assert_eq!(config.last_snapshot_slot, 99);
+    }
+
+    #[test]
+    fn release_is_capped_by_what_slashes_left() {
+        let mut profile = empty_profile();
+        profile.staked_amount = 300;
+        profile.slash_stake(5_000);
+        assert_eq!(profile.release_stake(100), 100);
+        assert_eq!(profile.release_stake(200), 50);
+        assert_eq!(profile.release_stake(100), 0);
     }
-    let ratio = (positive_vouches as i64 * 100) / (total as i64);
-    // Bonus ranges from -50 to +50 based on vouch ratio
-    (ratio - 50) / 2
 }
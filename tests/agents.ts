@@ -0,0 +1,161 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  Protocol,
+  TOKEN_PROGRAM_ID,
+  ata,
+  awardReputation,
+  expectError,
+  fixedBytes,
+  loadProgram,
+  nameRecord,
+  pda,
+  registerAgent,
+  setupProtocol,
+  stakeVouch,
+  tokenBalance,
+  transferTokens,
+  uniqueName,
+  vouchRecordPda,
+} from "./helpers";
+
+describe("agents", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = loadProgram(provider);
+
+  let protocol: Protocol;
+
+  before(async () => {
+    if (!program) return;
+    protocol = await setupProtocol(program, provider);
+  });
+
+  it("Registers under a chosen agent_id separate from the owner key", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const agent = await registerAgent(program, provider, uniqueName("agent"));
+    const profile: any = await program.account.agentProfile.fetch(agent.profile);
+    assert.ok(profile.agentId.equals(agent.agentId));
+    assert.ok(profile.owner.equals(agent.owner.publicKey));
+    assert.ok(!profile.agentId.equals(profile.owner));
+  });
+
+  it("Won't register an agent_id whose key didn't sign", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const agentId = Keypair.generate().publicKey;
+    await expectError(
+      program.methods
+        .registerAgent(fixedBytes("", 50), 0)
+        .accounts({
+          owner: provider.wallet.publicKey,
+          agentId,
+          agentProfile: pda(program, Buffer.from("agent"), agentId.toBuffer()),
+          tombstone: pda(program, Buffer.from("tombstone"), agentId.toBuffer()),
+          agentName: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc(),
+      "Missing signature"
+    );
+  });
+
+  it("Pays task rewards to the current owner after a key rotation", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const agent = await registerAgent(program, provider, uniqueName("agent"));
+    const newOwner = Keypair.generate();
+    await program.methods
+      .rotateAgentKeys(newOwner.publicKey, agent.owner.publicKey)
+      .accounts({ owner: agent.owner.publicKey, agentProfile: agent.profile })
+      .signers([agent.owner])
+      .rpc();
+
+    // The old owner is still the operating key, so it can accept the task
+    await awardReputation(program, provider, protocol, agent, 250);
+
+    assert.equal((await tokenBalance(provider, ata(protocol.mint, newOwner.publicKey))).toNumber(), 250);
+    assert.equal((await tokenBalance(provider, ata(protocol.mint, agent.agentId))).toNumber(), 0);
+    const profile: any = await program.account.agentProfile.fetch(agent.profile);
+    assert.equal(profile.reputationScore.toNumber(), 250);
+  });
+
+  it("Deregisters an agent, returning its stake and burning its NFT", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const name = uniqueName("leaver");
+    const agent = await registerAgent(program, provider, name);
+    const target = await registerAgent(program, provider, uniqueName("target"));
+    await transferTokens(provider, protocol.mint, agent.owner.publicKey, 1000);
+    const stake = await stakeVouch(program, provider, protocol, agent, target, 400, true);
+
+    const nftMint = Keypair.generate();
+    const nftTokenAccount = ata(nftMint.publicKey, agent.owner.publicKey);
+    await program.methods
+      .mintReputationNft(fixedBytes("https://example.com/nft", 100), 23)
+      .accounts({
+        agent: agent.owner.publicKey,
+        agentProfile: agent.profile,
+        reputationNft: pda(program, Buffer.from("reputation_nft"), agent.agentId.toBuffer()),
+        nftMint: nftMint.publicKey,
+        agentTokenAccount: nftTokenAccount,
+        mintAuthority: protocol.mintAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+        session: null,
+      })
+      .signers([agent.owner, nftMint])
+      .rpc();
+
+    const ownerTokens = ata(protocol.mint, agent.owner.publicKey);
+    const before = await tokenBalance(provider, ownerTokens);
+    await program.methods
+      .deregisterAgent()
+      .accounts({
+        owner: agent.owner.publicKey,
+        agentProfile: agent.profile,
+        tombstone: pda(program, Buffer.from("tombstone"), agent.agentId.toBuffer()),
+        agentName: nameRecord(program, name),
+        config: protocol.config,
+        ownerTokenAccount: ownerTokens,
+        stakeTokenAccount: stake.tokenAccount,
+        stakeEscrow: stake.escrow,
+        reputationNft: pda(program, Buffer.from("reputation_nft"), agent.agentId.toBuffer()),
+        nftMint: nftMint.publicKey,
+        nftTokenAccount,
+        mintAuthority: protocol.mintAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts([
+        { pubkey: vouchRecordPda(program, agent, target), isSigner: false, isWritable: true },
+      ])
+      .signers([agent.owner])
+      .rpc();
+
+    assert.equal((await tokenBalance(provider, ownerTokens)).sub(before).toNumber(), 400);
+    const connection = provider.connection;
+    assert.isNull(await connection.getAccountInfo(stake.tokenAccount));
+    assert.isNull(await connection.getAccountInfo(nftTokenAccount));
+    assert.equal((await connection.getTokenSupply(nftMint.publicKey)).value.amount, "0");
+    assert.isNull(await connection.getAccountInfo(agent.profile));
+    assert.isNull(await connection.getAccountInfo(nameRecord(program, name)));
+    const tombstone: any = await program.account.agentTombstone.fetch(
+      pda(program, Buffer.from("tombstone"), agent.agentId.toBuffer())
+    );
+    assert.ok(tombstone.owner.equals(agent.owner.publicKey));
+  });
+});
@@ -0,0 +1,110 @@
+import * as anchor from "@coral-xyz/anchor";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  Agent,
+  Protocol,
+  awardReputation,
+  loadProgram,
+  pda,
+  registerAgent,
+  setupProtocol,
+  u64Seed,
+  uniqueName,
+} from "./helpers";
+
+describe("disputes", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = loadProgram(provider);
+
+  let protocol: Protocol;
+
+  before(async () => {
+    if (!program) return;
+    protocol = await setupProtocol(program, provider);
+  });
+
+  // Disputes are numbered per target, so the same pair can dispute more than once
+  async function openDispute(challenger: Agent, target: Agent): Promise<PublicKey> {
+    const profile: any = await program.account.agentProfile.fetch(target.profile);
+    const dispute = pda(program, Buffer.from("dispute"), target.agentId.toBuffer(), u64Seed(profile.disputeCount));
+    await program.methods
+      .openDispute(Array(32).fill(1), { misconduct: {} })
+      .accounts({
+        challenger: challenger.owner.publicKey,
+        challengerProfile: challenger.profile,
+        targetProfile: target.profile,
+        target: target.agentId,
+        dispute,
+        config: protocol.config,
+        systemProgram: SystemProgram.programId,
+        session: null,
+      })
+      .signers([challenger.owner])
+      .rpc();
+    return dispute;
+  }
+
+  it("Lets the same challenger dispute a target again", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const challenger = await registerAgent(program, provider, uniqueName("challenger"));
+    const target = await registerAgent(program, provider, uniqueName("target"));
+    await awardReputation(program, provider, protocol, challenger, 5000);
+
+    const first = await openDispute(challenger, target);
+    const second = await openDispute(challenger, target);
+    assert.notEqual(first.toBase58(), second.toBase58());
+    assert.equal((await program.account.dispute.fetch(first) as any).id.toNumber(), 0);
+    assert.equal((await program.account.dispute.fetch(second) as any).id.toNumber(), 1);
+    const profile: any = await program.account.agentProfile.fetch(target.profile);
+    assert.equal(profile.disputeCount.toNumber(), 2);
+    assert.equal(profile.openDisputes, 2);
+  });
+
+  it("Weights juror votes by reputation at the dispute's snapshot", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const challenger = await registerAgent(program, provider, uniqueName("challenger"));
+    const target = await registerAgent(program, provider, uniqueName("target"));
+    const juror = await registerAgent(program, provider, uniqueName("juror"));
+    await awardReputation(program, provider, protocol, challenger, 5000);
+    await awardReputation(program, provider, protocol, juror, 2000);
+
+    const dispute = await openDispute(challenger, target);
+    // Reputation earned after the dispute opened carries no weight
+    await awardReputation(program, provider, protocol, juror, 3000);
+
+    await program.methods
+      .respondToDispute(Array(32).fill(2))
+      .accounts({
+        target: target.owner.publicKey,
+        targetProfile: target.profile,
+        dispute,
+        systemProgram: SystemProgram.programId,
+        session: null,
+      })
+      .signers([target.owner])
+      .rpc();
+    await program.methods
+      .voteDispute(true)
+      .accounts({
+        juror: juror.owner.publicKey,
+        jurorProfile: juror.profile,
+        dispute,
+        jurorVote: pda(program, Buffer.from("juror_vote"), dispute.toBuffer(), juror.agentId.toBuffer()),
+        systemProgram: SystemProgram.programId,
+        session: null,
+      })
+      .signers([juror.owner])
+      .rpc();
+
+    const account: any = await program.account.dispute.fetch(dispute);
+    assert.equal(account.votesUphold.toNumber(), 2000);
+  });
+});
@@ -0,0 +1,327 @@
+import * as anchor from "@coral-xyz/anchor";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  Agent,
+  Protocol,
+  TOKEN_PROGRAM_ID,
+  ata,
+  awardReputation,
+  createProposal,
+  expectError,
+  fundedKeypair,
+  loadProgram,
+  pda,
+  registerAgent,
+  setupProtocol,
+  tokenBalance,
+  transferTokens,
+  uniqueName,
+} from "./helpers";
+
+describe("governance", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = loadProgram(provider);
+
+  let protocol: Protocol;
+
+  before(async () => {
+    if (!program) return;
+    protocol = await setupProtocol(program, provider);
+  });
+
+  it("Refunds the deposit to the account that paid it", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const agent = await registerAgent(program, provider, uniqueName("proposer"));
+    const config: any = await program.account.protocolConfig.fetch(protocol.config);
+    // Enough reputation for the auto-vote alone to reach quorum
+    await awardReputation(program, provider, protocol, agent, config.quorumVotes.toNumber());
+
+    // Propose from a separate operating key, which pays the fee and deposit
+    const operator = await fundedKeypair(provider);
+    await program.methods
+      .rotateAgentKeys(agent.owner.publicKey, operator.publicKey)
+      .accounts({ owner: agent.owner.publicKey, agentProfile: agent.profile })
+      .signers([agent.owner])
+      .rpc();
+    const funding = config.proposalFee.add(config.proposalDeposit).toNumber();
+    await transferTokens(provider, protocol.mint, operator.publicKey, funding);
+
+    const proposal = await createProposal(
+      program, provider, protocol, agent, operator, { updateProposalFee: {} }, 100
+    );
+    assert.equal((await tokenBalance(provider, ata(protocol.mint, operator.publicKey))).toNumber(), 0);
+
+    await program.methods
+      .cancelProposal()
+      .accounts({ proposer: operator.publicKey, proposerProfile: agent.profile, proposal, session: null })
+      .signers([operator])
+      .rpc();
+
+    const ownerBefore = await tokenBalance(provider, ata(protocol.mint, agent.owner.publicKey));
+    const depositEscrow = pda(program, Buffer.from("escrow"), proposal.toBuffer());
+    await program.methods
+      .settleProposalDeposit()
+      .accounts({
+        settler: provider.wallet.publicKey,
+        proposal,
+        config: protocol.config,
+        depositEscrowTokenAccount: ata(protocol.mint, depositEscrow),
+        depositEscrow,
+        proposerTokenAccount: ata(protocol.mint, operator.publicKey),
+        treasury: protocol.treasury,
+        treasuryTokenAccount: protocol.treasuryTokenAccount,
+        proposerProfile: agent.profile,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    assert.equal(
+      (await tokenBalance(provider, ata(protocol.mint, operator.publicKey))).toNumber(),
+      config.proposalDeposit.toNumber()
+    );
+    const ownerAfter = await tokenBalance(provider, ata(protocol.mint, agent.owner.publicKey));
+    assert.ok(ownerAfter.eq(ownerBefore));
+    const profile: any = await program.account.agentProfile.fetch(agent.profile);
+    assert.equal(profile.openProposals, 0);
+  });
+
+  function voteRecord(proposal: PublicKey, agent: Agent): PublicKey {
+    return pda(program, Buffer.from("vote"), proposal.toBuffer(), agent.agentId.toBuffer());
+  }
+
+  async function delegateTo(delegator: Agent, delegate: Agent) {
+    await program.methods
+      .delegate(delegate.agentId)
+      .accounts({
+        delegator: delegator.owner.publicKey,
+        delegatorProfile: delegator.profile,
+        delegation: pda(program, Buffer.from("delegation"), delegator.agentId.toBuffer()),
+        systemProgram: SystemProgram.programId,
+        session: null,
+      })
+      .signers([delegator.owner])
+      .rpc();
+  }
+
+  // (delegation, delegator_profile, delegator_vote_record) as vote_proposal and
+  // cast_delegated_votes expect them
+  function delegatedVoteAccounts(proposal: PublicKey, delegator: Agent) {
+    return [
+      { pubkey: pda(program, Buffer.from("delegation"), delegator.agentId.toBuffer()), isSigner: false, isWritable: false },
+      { pubkey: delegator.profile, isSigner: false, isWritable: false },
+      { pubkey: voteRecord(proposal, delegator), isSigner: false, isWritable: true },
+    ];
+  }
+
+  async function newProposal(proposalType: any): Promise<{ proposer: Agent; proposal: PublicKey }> {
+    const config: any = await program.account.protocolConfig.fetch(protocol.config);
+    const proposer = await registerAgent(program, provider, uniqueName("proposer"));
+    await awardReputation(program, provider, protocol, proposer, config.minProposerReputation.toNumber());
+    await transferTokens(
+      provider, protocol.mint, proposer.owner.publicKey,
+      config.proposalFee.add(config.proposalDeposit).toNumber()
+    );
+    const proposal = await createProposal(
+      program, provider, protocol, proposer, proposer.owner, proposalType, 100
+    );
+    return { proposer, proposal };
+  }
+
+  async function vote(voter: Agent, proposal: PublicKey, delegators: Agent[] = []) {
+    await program.methods
+      .voteProposal({ for: {} })
+      .accounts({
+        voter: voter.owner.publicKey,
+        voterProfile: voter.profile,
+        proposal,
+        config: protocol.config,
+        proposalInstruction: null,
+        proposalBatch: null,
+        voteRecord: voteRecord(proposal, voter),
+        systemProgram: SystemProgram.programId,
+        session: null,
+      })
+      .remainingAccounts(delegators.flatMap((delegator) => delegatedVoteAccounts(proposal, delegator)))
+      .signers([voter.owner])
+      .rpc();
+  }
+
+  async function cancelAndSettle(proposer: Agent, proposal: PublicKey) {
+    await program.methods
+      .cancelProposal()
+      .accounts({ proposer: proposer.owner.publicKey, proposerProfile: proposer.profile, proposal, session: null })
+      .signers([proposer.owner])
+      .rpc();
+    const depositEscrow = pda(program, Buffer.from("escrow"), proposal.toBuffer());
+    await program.methods
+      .settleProposalDeposit()
+      .accounts({
+        settler: provider.wallet.publicKey,
+        proposal,
+        config: protocol.config,
+        depositEscrowTokenAccount: ata(protocol.mint, depositEscrow),
+        depositEscrow,
+        proposerTokenAccount: ata(protocol.mint, proposer.owner.publicKey),
+        treasury: protocol.treasury,
+        treasuryTokenAccount: protocol.treasuryTokenAccount,
+        proposerProfile: proposer.profile,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+  }
+
+  it("Casts delegated votes in batches after the delegate's own vote", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const delegate = await registerAgent(program, provider, uniqueName("delegate"));
+    await awardReputation(program, provider, protocol, delegate, 300);
+    const delegators: Agent[] = [];
+    for (let i = 0; i < 2; i++) {
+      const delegator = await registerAgent(program, provider, uniqueName("delegator"));
+      await awardReputation(program, provider, protocol, delegator, 200);
+      await delegateTo(delegator, delegate);
+      delegators.push(delegator);
+    }
+    const { proposal } = await newProposal({ updateProposalFee: {} });
+
+    await vote(delegate, proposal);
+    let tally: any = await program.account.governanceProposal.fetch(proposal);
+    const votesFor = tally.votesFor.toNumber();
+
+    // One delegator per call stands in for batches too large for a single transaction
+    for (const delegator of delegators) {
+      await program.methods
+        .castDelegatedVotes()
+        .accounts({
+          delegate: delegate.owner.publicKey,
+          delegateProfile: delegate.profile,
+          proposal,
+          config: protocol.config,
+          voteRecord: voteRecord(proposal, delegate),
+          systemProgram: SystemProgram.programId,
+          session: null,
+        })
+        .remainingAccounts(delegatedVoteAccounts(proposal, delegator))
+        .signers([delegate.owner])
+        .rpc();
+      const record: any = await program.account.voteRecord.fetch(voteRecord(proposal, delegator));
+      assert.ok(record.castBy.equals(delegate.agentId));
+    }
+
+    tally = await program.account.governanceProposal.fetch(proposal);
+    assert.equal(tally.votesFor.toNumber(), votesFor + 400);
+    const own: any = await program.account.voteRecord.fetch(voteRecord(proposal, delegate));
+    assert.equal(own.delegatedWeight.toNumber(), 400);
+  });
+
+  it("Lets anyone close a finished vote record, refunding whoever paid for it", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const delegate = await registerAgent(program, provider, uniqueName("delegate"));
+    const delegator = await registerAgent(program, provider, uniqueName("delegator"));
+    await awardReputation(program, provider, protocol, delegate, 300);
+    await awardReputation(program, provider, protocol, delegator, 200);
+    await delegateTo(delegator, delegate);
+    const { proposer, proposal } = await newProposal({ updateProposalFee: {} });
+    await vote(delegate, proposal, [delegator]);
+    await cancelAndSettle(proposer, proposal);
+
+    const record = voteRecord(proposal, delegator);
+    const recorded: any = await program.account.voteRecord.fetch(record);
+    assert.ok(recorded.payer.equals(delegate.owner.publicKey));
+    const rent = await provider.connection.getBalance(record);
+    const delegateBefore = await provider.connection.getBalance(delegate.owner.publicKey);
+    // Neither the voter nor the delegate has to sign, so deregistered agents' records still close
+    await program.methods
+      .closeVoteRecord()
+      .accounts({
+        caller: provider.wallet.publicKey,
+        voteRecord: record,
+        rentRecipient: delegate.owner.publicKey,
+        proposal,
+      })
+      .rpc();
+    const delegateAfter = await provider.connection.getBalance(delegate.owner.publicKey);
+    assert.equal(delegateAfter - delegateBefore, rent);
+  });
+
+  it("Requires the instruction PDA to close an ExecuteInstruction proposal", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const { proposer, proposal } = await newProposal({ executeInstruction: {} });
+    await cancelAndSettle(proposer, proposal);
+
+    const accounts = {
+      proposer: proposer.owner.publicKey,
+      proposerProfile: proposer.profile,
+      proposal,
+      proposalInstruction: null,
+      proposalBatch: null,
+      session: null,
+    };
+    await expectError(
+      program.methods.closeProposal().accounts(accounts).signers([proposer.owner]).rpc(),
+      "ProposalInstructionMissing"
+    );
+    // Nothing was attached, so passing the empty PDA is enough
+    await program.methods
+      .closeProposal()
+      .accounts({ ...accounts, proposalInstruction: pda(program, Buffer.from("proposal_ix"), proposal.toBuffer()) })
+      .signers([proposer.owner])
+      .rpc();
+    assert.isNull(await program.account.governanceProposal.fetchNullable(proposal));
+  });
+
+  it("Weights votes by reputation at the proposal snapshot", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const voter = await registerAgent(program, provider, uniqueName("voter"));
+    const latecomer = await registerAgent(program, provider, uniqueName("latecomer"));
+    await awardReputation(program, provider, protocol, voter, 200);
+    const { proposal } = await newProposal({ updateProposalFee: {} });
+
+    // Reputation earned after the snapshot doesn't count
+    await awardReputation(program, provider, protocol, voter, 300);
+    await awardReputation(program, provider, protocol, latecomer, 500);
+
+    const before: any = await program.account.governanceProposal.fetch(proposal);
+    await vote(voter, proposal);
+    const record: any = await program.account.voteRecord.fetch(voteRecord(proposal, voter));
+    assert.equal(record.reputation.toNumber(), 200);
+    const after: any = await program.account.governanceProposal.fetch(proposal);
+    assert.equal(after.votesFor.sub(before.votesFor).toNumber(), 200);
+    assert.equal(after.participation.sub(before.participation).toNumber(), 200);
+
+    await expectError(vote(latecomer, proposal), "NoReputationAtSnapshot");
+  });
+
+  it("Accepts new proposals however many are being voted on", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    // More than the old cap of 15 open snapshots, each in its own slot
+    for (let i = 0; i < 16; i++) {
+      await newProposal({ updateProposalFee: {} });
+    }
+    const { proposal } = await newProposal({ updateProposalFee: {} });
+    const account: any = await program.account.governanceProposal.fetch(proposal);
+    assert.deepEqual(account.status, { active: {} });
+    const config: any = await program.account.protocolConfig.fetch(protocol.config);
+    assert.ok(config.lastSnapshotSlot.eq(account.snapshotSlot));
+  });
+});
+
@@ -0,0 +1,503 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import {
+  Keypair,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_RENT_PUBKEY,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import { assert } from "chai";
+
+// Shared setup for the integration tests. Token instructions are built by hand
+// so the tests only depend on @coral-xyz/anchor and @solana/web3.js.
+export const TOKEN_PROGRAM_ID = anchor.utils.token.TOKEN_PROGRAM_ID;
+export const ASSOCIATED_TOKEN_PROGRAM_ID = anchor.utils.token.ASSOCIATED_PROGRAM_ID;
+
+// Supply minted to the test wallet before the mint is handed to the program
+const INITIAL_SUPPLY = new BN("1000000000000");
+
+export function loadProgram(provider: anchor.AnchorProvider): anchor.Program | null {
+  try {
+    const idl = require("../target/idl/agentreputation_dao.json");
+    return new anchor.Program(idl, provider) as anchor.Program;
+  } catch (e) {
+    return null;
+  }
+}
+
+export function pda(program: anchor.Program, ...seeds: (Buffer | Uint8Array)[]): PublicKey {
+  return PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+}
+
+export function u64Seed(value: number | BN): Buffer {
+  return new BN(value).toArrayLike(Buffer, "le", 8);
+}
+
+export function ata(mint: PublicKey, owner: PublicKey): PublicKey {
+  return anchor.utils.token.associatedAddress({ mint, owner });
+}
+
+export function fixedBytes(value: string, len: number): number[] {
+  const bytes = Array.from(Buffer.from(value, "utf8"));
+  return [...bytes, ...new Array(len - bytes.length).fill(0)];
+}
+
+export async function send(
+  provider: anchor.AnchorProvider,
+  ixs: TransactionInstruction[],
+  signers: Keypair[] = []
+): Promise<void> {
+  await provider.sendAndConfirm(new Transaction().add(...ixs), signers);
+}
+
+export async function fundedKeypair(provider: anchor.AnchorProvider, sol = 10): Promise<Keypair> {
+  const keypair = Keypair.generate();
+  const sig = await provider.connection.requestAirdrop(keypair.publicKey, sol * LAMPORTS_PER_SOL);
+  await provider.connection.confirmTransaction(sig, "confirmed");
+  return keypair;
+}
+
+export function createAtaIx(
+  payer: PublicKey,
+  mint: PublicKey,
+  owner: PublicKey
+): TransactionInstruction {
+  // CreateIdempotent
+  return new TransactionInstruction({
+    programId: ASSOCIATED_TOKEN_PROGRAM_ID,
+    keys: [
+      { pubkey: payer, isSigner: true, isWritable: true },
+      { pubkey: ata(mint, owner), isSigner: false, isWritable: true },
+      { pubkey: owner, isSigner: false, isWritable: false },
+      { pubkey: mint, isSigner: false, isWritable: false },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+    ],
+    data: Buffer.from([1]),
+  });
+}
+
+export async function createAta(
+  provider: anchor.AnchorProvider,
+  mint: PublicKey,
+  owner: PublicKey
+): Promise<PublicKey> {
+  await send(provider, [createAtaIx(provider.wallet.publicKey, mint, owner)]);
+  return ata(mint, owner);
+}
+
+export async function transferTokens(
+  provider: anchor.AnchorProvider,
+  mint: PublicKey,
+  to: PublicKey,
+  amount: number | BN
+): Promise<void> {
+  const data = Buffer.concat([Buffer.from([3]), u64Seed(amount)]);
+  await send(provider, [
+    createAtaIx(provider.wallet.publicKey, mint, to),
+    new TransactionInstruction({
+      programId: TOKEN_PROGRAM_ID,
+      keys: [
+        { pubkey: ata(mint, provider.wallet.publicKey), isSigner: false, isWritable: true },
+        { pubkey: ata(mint, to), isSigner: false, isWritable: true },
+        { pubkey: provider.wallet.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    }),
+  ]);
+}
+
+export async function tokenBalance(provider: anchor.AnchorProvider, account: PublicKey): Promise<BN> {
+  const info = await provider.connection.getAccountInfo(account);
+  if (!info) return new BN(0);
+  const balance = await provider.connection.getTokenAccountBalance(account);
+  return new BN(balance.value.amount);
+}
+
+// Mint the wallet's test supply, then hand mint authority to the program's PDA
+// so complete_task can mint reputation
+async function createReputationMint(
+  provider: anchor.AnchorProvider,
+  mintAuthority: PublicKey
+): Promise<PublicKey> {
+  const wallet = provider.wallet.publicKey;
+  const mint = Keypair.generate();
+  const space = 82;
+  const lamports = await provider.connection.getMinimumBalanceForRentExemption(space);
+  await send(
+    provider,
+    [
+      SystemProgram.createAccount({
+        fromPubkey: wallet,
+        newAccountPubkey: mint.publicKey,
+        space,
+        lamports,
+        programId: TOKEN_PROGRAM_ID,
+      }),
+      // InitializeMint2: decimals 0, no freeze authority
+      new TransactionInstruction({
+        programId: TOKEN_PROGRAM_ID,
+        keys: [{ pubkey: mint.publicKey, isSigner: false, isWritable: true }],
+        data: Buffer.concat([Buffer.from([20, 0]), wallet.toBuffer(), Buffer.from([0])]),
+      }),
+      createAtaIx(wallet, mint.publicKey, wallet),
+      // MintTo
+      new TransactionInstruction({
+        programId: TOKEN_PROGRAM_ID,
+        keys: [
+          { pubkey: mint.publicKey, isSigner: false, isWritable: true },
+          { pubkey: ata(mint.publicKey, wallet), isSigner: false, isWritable: true },
+          { pubkey: wallet, isSigner: true, isWritable: false },
+        ],
+        data: Buffer.concat([Buffer.from([7]), u64Seed(INITIAL_SUPPLY)]),
+      }),
+      // SetAuthority(MintTokens) to the program
+      new TransactionInstruction({
+        programId: TOKEN_PROGRAM_ID,
+        keys: [
+          { pubkey: mint.publicKey, isSigner: false, isWritable: true },
+          { pubkey: wallet, isSigner: true, isWritable: false },
+        ],
+        data: Buffer.concat([Buffer.from([6, 0, 1]), mintAuthority.toBuffer()]),
+      }),
+    ],
+    [mint]
+  );
+  return mint.publicKey;
+}
+
+export function defaultConfig(): any {
+  const zero = new BN(0);
+  return {
+    authority: PublicKey.default,
+    reputationMint: PublicKey.default,
+    minReputationForVouching: zero,
+    decayRatePerDay: new BN(10),
+    vouchLockupPeriod: zero, // Lets the tests withdraw and deregister right away
+    slashThreshold: zero,
+    maxTrustMultiplier: zero,
+    taskFraudSlashBps: zero,
+    collusionSlashBps: zero,
+    maliciousSlashBps: zero,
+    vouchFeeBps: zero,
+    proposalFee: zero,
+    proposalDeposit: zero,
+    votingPeriod: zero,
+    quorumVotes: zero,
+    quorumBps: zero,
+    majorityBps: zero,
+    minProposerReputation: zero,
+    minVoterReputation: zero,
+    totalReputation: zero,
+    timelockDelay: zero,
+    guardians: new Array(5).fill(PublicKey.default),
+    guardianCount: 0,
+    allowedVotingModes: 0,
+    proposalCount: zero,
+    lastSnapshotSlot: zero,
+    bump: 0,
+  };
+}
+
+export interface Protocol {
+  config: PublicKey;
+  mint: PublicKey;
+  mintAuthority: PublicKey;
+  treasury: PublicKey;
+  treasuryTokenAccount: PublicKey;
+}
+
+// Config, mint and treasury are singletons, so every test file shares them
+export async function setupProtocol(
+  program: anchor.Program,
+  provider: anchor.AnchorProvider
+): Promise<Protocol> {
+  const config = pda(program, Buffer.from("config"));
+  const mintAuthority = pda(program, Buffer.from("mint_authority"));
+  const treasury = pda(program, Buffer.from("treasury"));
+
+  let existing: any = await program.account.protocolConfig.fetchNullable(config);
+  if (!existing) {
+    const mint = await createReputationMint(provider, mintAuthority);
+    await program.methods
+      .initialize(defaultConfig())
+      .accounts({
+        authority: provider.wallet.publicKey,
+        config,
+        reputationMint: mint,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    existing = await program.account.protocolConfig.fetch(config);
+  }
+  const mint: PublicKey = existing.reputationMint;
+  const treasuryTokenAccount = ata(mint, treasury);
+
+  if (!(await program.account.treasury.fetchNullable(treasury))) {
+    await program.methods
+      .initializeTreasury()
+      .accounts({
+        authority: provider.wallet.publicKey,
+        config,
+        treasury,
+        treasuryTokenAccount,
+        reputationMint: mint,
+      })
+      .rpc();
+  }
+
+  return { config, mint, mintAuthority, treasury, treasuryTokenAccount };
+}
+
+export interface Agent {
+  owner: Keypair;
+  agentId: PublicKey;
+  profile: PublicKey;
+}
+
+// AgentName PDA, keyed by the hash of the lowercased name
+export function nameRecord(program: anchor.Program, name: string): PublicKey {
+  const nameHash = anchor.utils.sha256.hash(name.toLowerCase());
+  return pda(program, Buffer.from("name"), Buffer.from(nameHash, "hex"));
+}
+
+// An empty name registers the agent without one
+export async function registerAgent(
+  program: anchor.Program,
+  provider: anchor.AnchorProvider,
+  name: string
+): Promise<Agent> {
+  const owner = await fundedKeypair(provider);
+  // agent_id is a stable identifier that signs registration and is then discarded
+  const agentKey = Keypair.generate();
+  const agentId = agentKey.publicKey;
+  const profile = pda(program, Buffer.from("agent"), agentId.toBuffer());
+
+  await program.methods
+    .registerAgent(fixedBytes(name, 50), name.length)
+    .accounts({
+      owner: owner.publicKey,
+      agentId,
+      agentProfile: profile,
+      tombstone: pda(program, Buffer.from("tombstone"), agentId.toBuffer()),
+      agentName: name ? nameRecord(program, name) : null,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([owner, agentKey])
+    .rpc();
+
+  return { owner, agentId, profile };
+}
+
+// The test wallet acts as task issuer, registered on first use
+export async function ensureTaskIssuer(
+  program: anchor.Program,
+  provider: anchor.AnchorProvider,
+  protocol: Protocol
+): Promise<PublicKey> {
+  const wallet = provider.wallet.publicKey;
+  const taskIssuer = pda(program, Buffer.from("task_issuer"), wallet.toBuffer());
+  if (!(await program.account.taskIssuer.fetchNullable(taskIssuer))) {
+    await program.methods
+      .registerTaskIssuer(new BN("1000000000000"))
+      .accounts({
+        authority: wallet,
+        issuer: wallet,
+        taskIssuer,
+        config: protocol.config,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  }
+  return taskIssuer;
+}
+
+// Create a task from the wallet issuer
+export async function openTask(
+  program: anchor.Program,
+  provider: anchor.AnchorProvider,
+  protocol: Protocol,
+  budget: number
+): Promise<PublicKey> {
+  const wallet = provider.wallet.publicKey;
+  const taskIssuer = await ensureTaskIssuer(program, provider, protocol);
+  const taskId = uniqueName("task");
+  const taskRecord = pda(program, Buffer.from("task"), wallet.toBuffer(), Buffer.from(taskId));
+  await program.methods
+    .createTask(fixedBytes(taskId, 100), taskId.length, new BN(budget))
+    .accounts({ issuer: wallet, taskIssuer, taskRecord, systemProgram: SystemProgram.programId })
+    .rpc();
+  return taskRecord;
+}
+
+// Create a task from the wallet issuer and have `agent` accept it
+export async function acceptedTask(
+  program: anchor.Program,
+  provider: anchor.AnchorProvider,
+  protocol: Protocol,
+  agent: Agent,
+  budget: number
+): Promise<PublicKey> {
+  const taskRecord = await openTask(program, provider, protocol, budget);
+  await program.methods
+    .acceptTask()
+    .accounts({ agent: agent.owner.publicKey, agentProfile: agent.profile, taskRecord, session: null })
+    .signers([agent.owner])
+    .rpc();
+  return taskRecord;
+}
+
+// complete_task accounts for `taskRecord`, minting to the agent owner's token account
+export async function completeTaskAccounts(
+  program: anchor.Program,
+  provider: anchor.AnchorProvider,
+  protocol: Protocol,
+  agent: Agent,
+  taskRecord: PublicKey,
+  approver: PublicKey = provider.wallet.publicKey
+): Promise<any> {
+  const owner = (await program.account.agentProfile.fetch(agent.profile) as any).owner as PublicKey;
+  return {
+    approver,
+    taskIssuer: pda(program, Buffer.from("task_issuer"), provider.wallet.publicKey.toBuffer()),
+    taskRecord,
+    agentProfile: agent.profile,
+    agent: agent.agentId,
+    config: protocol.config,
+    mintAuthority: protocol.mintAuthority,
+    reputationMint: protocol.mint,
+    agentTokenAccount: ata(protocol.mint, owner),
+    tokenProgram: TOKEN_PROGRAM_ID,
+  };
+}
+
+// Approving a task mints reputation tokens to the agent owner's token account
+export async function awardReputation(
+  program: anchor.Program,
+  provider: anchor.AnchorProvider,
+  protocol: Protocol,
+  agent: Agent,
+  amount: number
+): Promise<void> {
+  const taskRecord = await acceptedTask(program, provider, protocol, agent, amount);
+  const owner = (await program.account.agentProfile.fetch(agent.profile) as any).owner as PublicKey;
+  await program.methods
+    .completeTask(new BN(amount))
+    .preInstructions([createAtaIx(provider.wallet.publicKey, protocol.mint, owner)])
+    .accounts(await completeTaskAccounts(program, provider, protocol, agent, taskRecord))
+    .rpc();
+}
+
+// Create a proposal signed by `signer` (the owner, operating key or a session key),
+// paying the fee and deposit from the signer's token account
+export async function createProposal(
+  program: anchor.Program,
+  provider: anchor.AnchorProvider,
+  protocol: Protocol,
+  agent: Agent,
+  signer: Keypair,
+  proposalType: any,
+  newValue: number,
+  votingMode: any = { linear: {} }
+): Promise<PublicKey> {
+  const config: any = await program.account.protocolConfig.fetch(protocol.config);
+  const proposal = pda(program, Buffer.from("proposal"), u64Seed(config.proposalCount));
+  const depositEscrow = pda(program, Buffer.from("escrow"), proposal.toBuffer());
+  await program.methods
+    .createProposal(proposalType, new BN(newValue), PublicKey.default, votingMode, fixedBytes("test", 200), 4)
+    .preInstructions([createAtaIx(provider.wallet.publicKey, protocol.mint, depositEscrow)])
+    .accounts({
+      proposer: signer.publicKey,
+      proposerProfile: agent.profile,
+      config: protocol.config,
+      proposal,
+      proposerVoteRecord: pda(program, Buffer.from("vote"), proposal.toBuffer(), agent.agentId.toBuffer()),
+      proposerTokenAccount: ata(protocol.mint, signer.publicKey),
+      treasury: protocol.treasury,
+      treasuryTokenAccount: protocol.treasuryTokenAccount,
+      depositEscrowTokenAccount: ata(protocol.mint, depositEscrow),
+      depositEscrow,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      session: null,
+    })
+    .signers([signer])
+    .rpc();
+  return proposal;
+}
+
+export interface StakeEscrow {
+  escrow: PublicKey;
+  tokenAccount: PublicKey;
+}
+
+// Every vouch an agent makes is staked into this one escrow
+export async function stakeEscrow(
+  program: anchor.Program,
+  provider: anchor.AnchorProvider,
+  protocol: Protocol,
+  agentId: PublicKey
+): Promise<StakeEscrow> {
+  const escrow = pda(program, Buffer.from("stake"), agentId.toBuffer());
+  const tokenAccount = await createAta(provider, protocol.mint, escrow);
+  return { escrow, tokenAccount };
+}
+
+export function vouchRecordPda(program: anchor.Program, voucher: Agent, target: Agent): PublicKey {
+  return pda(program, Buffer.from("vouch"), voucher.agentId.toBuffer(), target.agentId.toBuffer());
+}
+
+// Vouch for or against `target`, staking `amount` from the voucher owner's token account
+export async function stakeVouch(
+  program: anchor.Program,
+  provider: anchor.AnchorProvider,
+  protocol: Protocol,
+  voucher: Agent,
+  target: Agent,
+  amount: number,
+  positive: boolean
+): Promise<StakeEscrow> {
+  const stake = await stakeEscrow(program, provider, protocol, voucher.agentId);
+  const accounts: any = {
+    voucher: voucher.owner.publicKey,
+    voucherProfile: voucher.profile,
+    vouchRecord: vouchRecordPda(program, voucher, target),
+    config: protocol.config,
+    voucherTokenAccount: ata(protocol.mint, voucher.owner.publicKey),
+    stakeTokenAccount: stake.tokenAccount,
+    stakeEscrow: stake.escrow,
+    treasury: protocol.treasury,
+    treasuryTokenAccount: protocol.treasuryTokenAccount,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    systemProgram: SystemProgram.programId,
+    rent: SYSVAR_RENT_PUBKEY,
+    session: null,
+  };
+  if (positive) {
+    accounts.vouchedForProfile = target.profile;
+    accounts.vouchedFor = target.agentId;
+    await program.methods.vouchFor(new BN(amount)).accounts(accounts).signers([voucher.owner]).rpc();
+  } else {
+    accounts.vouchedAgainstProfile = target.profile;
+    accounts.vouchedAgainst = target.agentId;
+    await program.methods.vouchAgainst(new BN(amount)).accounts(accounts).signers([voucher.owner]).rpc();
+  }
+  return stake;
+}
+
+export function uniqueName(prefix: string): string {
+  return `${prefix}_${Math.random().toString(36).slice(2, 10)}`;
+}
+
+export async function expectError(promise: Promise<any>, code: string): Promise<void> {
+  try {
+    await promise;
+  } catch (e: any) {
+    assert.include(e.toString(), code);
+    return;
+  }
+  assert.fail(`expected ${code}`);
+}
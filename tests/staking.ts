@@ -0,0 +1,223 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  Agent,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  Protocol,
+  TOKEN_PROGRAM_ID,
+  ata,
+  loadProgram,
+  pda,
+  registerAgent,
+  setupProtocol,
+  stakeVouch,
+  tokenBalance,
+  transferTokens,
+  uniqueName,
+  vouchRecordPda,
+} from "./helpers";
+
+describe("staking", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = loadProgram(provider);
+
+  let protocol: Protocol;
+
+  before(async () => {
+    if (!program) return;
+    protocol = await setupProtocol(program, provider);
+  });
+
+  function vouchRecord(voucher: Agent, target: Agent): PublicKey {
+    return vouchRecordPda(program, voucher, target);
+  }
+
+  async function vouch(voucher: Agent, target: Agent, amount: number, positive: boolean) {
+    return stakeVouch(program, provider, protocol, voucher, target, amount, positive);
+  }
+
+  async function resolve(challenger: Agent, target: Agent, upheld: boolean) {
+    const challengerStake = pda(program, Buffer.from("stake"), challenger.agentId.toBuffer());
+    const targetStake = pda(program, Buffer.from("stake"), target.agentId.toBuffer());
+    await program.methods
+      .resolveChallenge(upheld)
+      .accounts({
+        authority: provider.wallet.publicKey,
+        config: protocol.config,
+        challengeRecord: vouchRecord(challenger, target),
+        challenger: challenger.agentId,
+        target: target.agentId,
+        challengerProfile: challenger.profile,
+        targetProfile: target.profile,
+        challengerTokenAccount: ata(protocol.mint, challenger.owner.publicKey),
+        challengerStakeTokenAccount: ata(protocol.mint, challengerStake),
+        challengerStakeEscrow: challengerStake,
+        targetStakeTokenAccount: ata(protocol.mint, targetStake),
+        targetStakeEscrow: targetStake,
+        reputationMint: protocol.mint,
+        treasury: protocol.treasury,
+        treasuryTokenAccount: protocol.treasuryTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  }
+
+  // Signed by the owner unless another of the voucher's keys is given
+  async function withdraw(voucher: Agent, target: Agent, signer: Keypair = voucher.owner) {
+    const stakeEscrow = pda(program, Buffer.from("stake"), voucher.agentId.toBuffer());
+    await program.methods
+      .withdrawVouch()
+      .accounts({
+        voucher: signer.publicKey,
+        voucherProfile: voucher.profile,
+        owner: voucher.owner.publicKey,
+        vouchRecord: vouchRecord(voucher, target),
+        vouchedFor: target.agentId,
+        config: protocol.config,
+        voucherTokenAccount: ata(protocol.mint, voucher.owner.publicKey),
+        stakeTokenAccount: ata(protocol.mint, stakeEscrow),
+        stakeEscrow,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        session: null,
+      })
+      .signers([signer])
+      .rpc();
+  }
+
+  it("Stakes vouches into the voucher's escrow and returns them on withdrawal", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const voucher = await registerAgent(program, provider, uniqueName("voucher"));
+    const target = await registerAgent(program, provider, uniqueName("target"));
+    await transferTokens(provider, protocol.mint, voucher.owner.publicKey, 1000);
+
+    const stake = await vouch(voucher, target, 400, true);
+    assert.equal((await tokenBalance(provider, stake.tokenAccount)).toNumber(), 400);
+    let profile: any = await program.account.agentProfile.fetch(voucher.profile);
+    assert.equal(profile.stakedAmount.toNumber(), 400);
+    assert.equal(profile.openVouches, 1);
+
+    await withdraw(voucher, target);
+
+    assert.equal((await tokenBalance(provider, stake.tokenAccount)).toNumber(), 0);
+    profile = await program.account.agentProfile.fetch(voucher.profile);
+    assert.equal(profile.stakedAmount.toNumber(), 0);
+    assert.equal(profile.openVouches, 0);
+  });
+
+  it("Returns withdrawn stake and rent to the owner when the operating key signs", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const voucher = await registerAgent(program, provider, uniqueName("voucher"));
+    const target = await registerAgent(program, provider, uniqueName("target"));
+    await transferTokens(provider, protocol.mint, voucher.owner.publicKey, 1000);
+    await vouch(voucher, target, 400, true);
+
+    const operatingKey = Keypair.generate();
+    await program.methods
+      .rotateAgentKeys(voucher.owner.publicKey, operatingKey.publicKey)
+      .accounts({ owner: voucher.owner.publicKey, agentProfile: voucher.profile })
+      .signers([voucher.owner])
+      .rpc();
+
+    const record = vouchRecord(voucher, target);
+    const rent = await provider.connection.getBalance(record);
+    const tokensBefore = await tokenBalance(provider, ata(protocol.mint, voucher.owner.publicKey));
+    const lamportsBefore = await provider.connection.getBalance(voucher.owner.publicKey);
+    await withdraw(voucher, target, operatingKey);
+
+    const tokensAfter = await tokenBalance(provider, ata(protocol.mint, voucher.owner.publicKey));
+    assert.equal(tokensAfter.sub(tokensBefore).toNumber(), 400);
+    const lamportsAfter = await provider.connection.getBalance(voucher.owner.publicKey);
+    assert.equal(lamportsAfter - lamportsBefore, rent);
+  });
+
+  it("Pays an upheld challenge out of the target's stake", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const challenger = await registerAgent(program, provider, uniqueName("challenger"));
+    const target = await registerAgent(program, provider, uniqueName("target"));
+    const other = await registerAgent(program, provider, uniqueName("other"));
+    await transferTokens(provider, protocol.mint, challenger.owner.publicKey, 1000);
+    await transferTokens(provider, protocol.mint, target.owner.publicKey, 1000);
+
+    const targetStake = await vouch(target, other, 500, true);
+    await vouch(challenger, target, 300, false);
+    const before = await tokenBalance(provider, ata(protocol.mint, challenger.owner.publicKey));
+
+    await resolve(challenger, target, true);
+
+    const after = await tokenBalance(provider, ata(protocol.mint, challenger.owner.publicKey));
+    assert.equal(after.sub(before).toNumber(), 300);
+    assert.equal((await tokenBalance(provider, targetStake.tokenAccount)).toNumber(), 200);
+    const profile: any = await program.account.agentProfile.fetch(target.profile);
+    assert.equal(profile.stakedAmount.toNumber(), 200);
+    assert.equal(profile.openDisputes, 0);
+  });
+
+  it("Caps an upheld challenge's reward at the target's stake", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const challenger = await registerAgent(program, provider, uniqueName("challenger"));
+    const target = await registerAgent(program, provider, uniqueName("target"));
+    const other = await registerAgent(program, provider, uniqueName("other"));
+    await transferTokens(provider, protocol.mint, challenger.owner.publicKey, 1000);
+    await transferTokens(provider, protocol.mint, target.owner.publicKey, 1000);
+
+    const targetStake = await vouch(target, other, 100, true);
+    await vouch(challenger, target, 300, false);
+    const before = await tokenBalance(provider, ata(protocol.mint, challenger.owner.publicKey));
+    const treasuryBefore = await tokenBalance(provider, protocol.treasuryTokenAccount);
+
+    await resolve(challenger, target, true);
+
+    // Only the target's 100 staked; the treasury pays nothing
+    const after = await tokenBalance(provider, ata(protocol.mint, challenger.owner.publicKey));
+    assert.equal(after.sub(before).toNumber(), 100);
+    assert.equal((await tokenBalance(provider, targetStake.tokenAccount)).toNumber(), 0);
+    const treasuryAfter = await tokenBalance(provider, protocol.treasuryTokenAccount);
+    assert.ok(treasuryAfter.eq(treasuryBefore));
+    const profile: any = await program.account.agentProfile.fetch(target.profile);
+    assert.equal(profile.openDisputes, 0);
+    assert.equal(profile.negativeVouches.toNumber(), 1);
+    const record: any = await program.account.vouchRecord.fetch(vouchRecord(challenger, target));
+    assert.deepEqual(record.challengeStatus, { upheld: {} });
+  });
+
+  it("Forfeits a rejected challenge's stake to the treasury", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const challenger = await registerAgent(program, provider, uniqueName("challenger"));
+    const target = await registerAgent(program, provider, uniqueName("target"));
+    await transferTokens(provider, protocol.mint, challenger.owner.publicKey, 1000);
+
+    const stake = await vouch(challenger, target, 300, false);
+    const treasuryBefore = await tokenBalance(provider, protocol.treasuryTokenAccount);
+
+    await resolve(challenger, target, false);
+
+    const treasuryAfter = await tokenBalance(provider, protocol.treasuryTokenAccount);
+    assert.equal(treasuryAfter.sub(treasuryBefore).toNumber(), 300);
+    assert.equal((await tokenBalance(provider, stake.tokenAccount)).toNumber(), 0);
+    const profile: any = await program.account.agentProfile.fetch(challenger.profile);
+    assert.equal(profile.stakedAmount.toNumber(), 0);
+    const targetProfile: any = await program.account.agentProfile.fetch(target.profile);
+    assert.equal(targetProfile.negativeVouches.toNumber(), 0);
+    assert.equal(targetProfile.openDisputes, 0);
+  });
+});
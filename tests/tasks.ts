@@ -0,0 +1,161 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { SystemProgram } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  Protocol,
+  acceptedTask,
+  completeTaskAccounts,
+  createAta,
+  ensureTaskIssuer,
+  expectError,
+  fixedBytes,
+  loadProgram,
+  openTask,
+  pda,
+  registerAgent,
+  setupProtocol,
+  tokenBalance,
+  uniqueName,
+} from "./helpers";
+
+describe("tasks", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = loadProgram(provider);
+
+  let protocol: Protocol;
+
+  before(async () => {
+    if (!program) return;
+    protocol = await setupProtocol(program, provider);
+  });
+
+  it("Only lets the issuer approve, not the agent itself", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const agent = await registerAgent(program, provider, uniqueName("agent"));
+    const taskRecord = await acceptedTask(program, provider, protocol, agent, 500);
+    const agentTokens = await createAta(provider, protocol.mint, agent.owner.publicKey);
+
+    const selfApproval = await completeTaskAccounts(
+      program, provider, protocol, agent, taskRecord, agent.owner.publicKey
+    );
+    await expectError(
+      program.methods.completeTask(new BN(500)).accounts(selfApproval).signers([agent.owner]).rpc(),
+      "NotTaskApprover"
+    );
+    assert.equal((await tokenBalance(provider, agentTokens)).toNumber(), 0);
+    const profile: any = await program.account.agentProfile.fetch(agent.profile);
+    assert.equal(profile.reputationScore.toNumber(), 0);
+  });
+
+  it("Awards at most the budget and returns the rest to the issuer", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const agent = await registerAgent(program, provider, uniqueName("agent"));
+    const taskIssuer = await ensureTaskIssuer(program, provider, protocol);
+    const allowanceBefore = (await program.account.taskIssuer.fetch(taskIssuer) as any).reputationAllowance;
+    const taskRecord = await acceptedTask(program, provider, protocol, agent, 500);
+    const agentTokens = await createAta(provider, protocol.mint, agent.owner.publicKey);
+    const accounts = await completeTaskAccounts(program, provider, protocol, agent, taskRecord);
+
+    await expectError(
+      program.methods.completeTask(new BN(501)).accounts(accounts).rpc(),
+      "InvalidReputationAmount"
+    );
+    await program.methods.completeTask(new BN(300)).accounts(accounts).rpc();
+
+    assert.equal((await tokenBalance(provider, agentTokens)).toNumber(), 300);
+    const task: any = await program.account.taskRecord.fetch(taskRecord);
+    assert.equal(task.reputationEarned.toNumber(), 300);
+    assert.ok(task.approvedBy.equals(provider.wallet.publicKey));
+    const allowanceAfter = (await program.account.taskIssuer.fetch(taskIssuer) as any).reputationAllowance;
+    assert.equal(allowanceBefore.sub(allowanceAfter).toNumber(), 300);
+  });
+
+  it("Doesn't let the agent's owner approve as the issuer's reviewer", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const agent = await registerAgent(program, provider, uniqueName("agent"));
+    const taskIssuer = await ensureTaskIssuer(program, provider, protocol);
+    await program.methods
+      .addTaskReviewer(agent.owner.publicKey)
+      .accounts({ issuer: provider.wallet.publicKey, taskIssuer })
+      .rpc();
+    const taskRecord = await acceptedTask(program, provider, protocol, agent, 500);
+    await createAta(provider, protocol.mint, agent.owner.publicKey);
+
+    const accounts = await completeTaskAccounts(
+      program, provider, protocol, agent, taskRecord, agent.owner.publicKey
+    );
+    await expectError(
+      program.methods.completeTask(new BN(500)).accounts(accounts).signers([agent.owner]).rpc(),
+      "SelfDealingNotAllowed"
+    );
+  });
+
+  it("Rejects an oversized task id length without panicking", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const taskIssuer = await ensureTaskIssuer(program, provider, protocol);
+    const taskId = fixedBytes(uniqueName("task"), 100);
+    // The program clamps the seed to the 32-byte maximum before rejecting the length
+    await expectError(
+      program.methods
+        .createTask(taskId, 101, new BN(100))
+        .accounts({
+          issuer: provider.wallet.publicKey,
+          taskIssuer,
+          taskRecord: pda(program, Buffer.from("task"), provider.wallet.publicKey.toBuffer(), Buffer.from(taskId.slice(0, 32))),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc(),
+      "TaskIdTooLong"
+    );
+  });
+
+  it("Cancels an unaccepted task and releases its budget", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const taskIssuer = await ensureTaskIssuer(program, provider, protocol);
+    const allowanceBefore = (await program.account.taskIssuer.fetch(taskIssuer) as any).reputationAllowance;
+    const taskRecord = await openTask(program, provider, protocol, 700);
+
+    await program.methods
+      .cancelTask()
+      .accounts({ issuer: provider.wallet.publicKey, taskIssuer, taskRecord })
+      .rpc();
+
+    assert.isNull(await program.account.taskRecord.fetchNullable(taskRecord));
+    const allowanceAfter = (await program.account.taskIssuer.fetch(taskIssuer) as any).reputationAllowance;
+    assert.ok(allowanceAfter.eq(allowanceBefore));
+  });
+
+  it("Doesn't cancel a task once accepted", async () => {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const agent = await registerAgent(program, provider, uniqueName("agent"));
+    const taskIssuer = await ensureTaskIssuer(program, provider, protocol);
+    const taskRecord = await acceptedTask(program, provider, protocol, agent, 500);
+    await expectError(
+      program.methods
+        .cancelTask()
+        .accounts({ issuer: provider.wallet.publicKey, taskIssuer, taskRecord })
+        .rpc(),
+      "InvalidTaskStatus"
+    );
+  });
+});
@@ -0,0 +1,330 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import { assert } from "chai";
+import { randomBytes } from "crypto";
+import * as fs from "fs";
+import * as path from "path";
+import {
+  Agent,
+  Protocol,
+  awardReputation,
+  expectError,
+  loadProgram,
+  pda,
+  registerAgent,
+  setupProtocol,
+  uniqueName,
+} from "./helpers";
+
+// Runs the circuits from circuits/README.md against the on-chain Groth16
+// verifier. Skipped until circuits/build/ has been generated.
+const BUILD_DIR = path.join(__dirname, "..", "circuits", "build");
+const BN254_SCALAR_MODULUS = new BN(
+  "21888242871839275222246405745257275088548364400416034343698204186575808495617"
+);
+
+function circuitFiles(name: string) {
+  return {
+    wasm: path.join(BUILD_DIR, `${name}_js`, `${name}.wasm`),
+    zkey: path.join(BUILD_DIR, `${name}.zkey`),
+    vk: path.join(BUILD_DIR, `${name}_vk.json`),
+  };
+}
+
+// snarkjs emits decimal strings; the program expects 32-byte big-endian field elements
+function fieldBytes(value: string): number[] {
+  return Array.from(new BN(value).toArrayLike(Buffer, "be", 32));
+}
+
+function g1Bytes(point: string[]): number[] {
+  return [...fieldBytes(point[0]), ...fieldBytes(point[1])];
+}
+
+// EIP-197 order: imaginary component first
+function g2Bytes(point: string[][]): number[] {
+  return [
+    ...fieldBytes(point[0][1]),
+    ...fieldBytes(point[0][0]),
+    ...fieldBytes(point[1][1]),
+    ...fieldBytes(point[1][0]),
+  ];
+}
+
+function padTo(bytes: number[], len: number): number[] {
+  return [...bytes, ...new Array(len - bytes.length).fill(0)];
+}
+
+function vkBytes(vk: any): number[] {
+  return [
+    ...g1Bytes(vk.vk_alpha_1),
+    ...g2Bytes(vk.vk_beta_2),
+    ...g2Bytes(vk.vk_gamma_2),
+    ...g2Bytes(vk.vk_delta_2),
+    ...vk.IC.flatMap((point: string[]) => g1Bytes(point)),
+  ];
+}
+
+function proofBytes(proof: any): number[] {
+  return [...g1Bytes(proof.pi_a), ...g2Bytes(proof.pi_b), ...g1Bytes(proof.pi_c)];
+}
+
+describe("zk_groth16", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  let program: anchor.Program | null = null;
+  let protocol: Protocol;
+  let snarkjs: any = null;
+  let poseidon: any = null;
+
+  before(async () => {
+    try {
+      program = loadProgram(provider);
+      snarkjs = require("snarkjs");
+      poseidon = await require("circomlibjs").buildPoseidon();
+      for (const name of ["reputation_above", "reputation_commitment"]) {
+        const files = circuitFiles(name);
+        assert.isTrue(fs.existsSync(files.wasm) && fs.existsSync(files.zkey) && fs.existsSync(files.vk));
+      }
+      protocol = await setupProtocol(program!, provider);
+    } catch (e) {
+      console.log("Program or circuit build missing, skipping ZK tests");
+      program = null;
+    }
+  });
+
+  // Register or rotate the verification key for `circuit` at `seed`
+  async function registerKey(circuit: any, seed: number, name: string): Promise<PublicKey> {
+    const bytes = vkBytes(JSON.parse(fs.readFileSync(circuitFiles(name).vk, "utf8")));
+    const zkRegistry = pda(program!, Buffer.from("zk_registry"), Buffer.from([seed]));
+    if (await program!.account.zkVerificationKey.fetchNullable(zkRegistry)) {
+      await program!.methods
+        .updateVerificationKey(new Array(32).fill(0), padTo(bytes, 1000), bytes.length)
+        .accounts({ authority: provider.wallet.publicKey, zkRegistry })
+        .rpc();
+    } else {
+      await program!.methods
+        .initializeZkRegistry(circuit, new Array(32).fill(0), padTo(bytes, 1000), bytes.length)
+        .accounts({
+          authority: provider.wallet.publicKey,
+          config: protocol.config,
+          zkRegistry,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+    return zkRegistry;
+  }
+
+  async function prove(name: string, input: any): Promise<number[]> {
+    const files = circuitFiles(name);
+    const { proof } = await snarkjs.groth16.fullProve(input, files.wasm, files.zkey);
+    return proofBytes(proof);
+  }
+
+  // Commit to the agent's current reputation under a fresh secret blinding factor
+  async function commit(agent: Agent, zkRegistry: PublicKey): Promise<{ blinding: string; commitment: string }> {
+    const profile: any = await program!.account.agentProfile.fetch(agent.profile);
+    const blinding = new BN(randomBytes(32)).mod(BN254_SCALAR_MODULUS).toString();
+    const commitment = poseidon.F.toString(poseidon([profile.reputationScore.toString(), blinding]));
+    const proof = await prove("reputation_commitment", {
+      reputation: profile.reputationScore.toString(),
+      commitment,
+      blinding,
+    });
+    await program!.methods
+      .commitReputation(fieldBytes(commitment), padTo(proof, 500), proof.length)
+      .accounts({ prover: agent.owner.publicKey, proverProfile: agent.profile, zkRegistry, session: null })
+      .signers([agent.owner])
+      .rpc();
+    return { blinding, commitment };
+  }
+
+  async function submitAbove(agent: Agent, zkRegistry: PublicKey, threshold: BN, proof: number[]) {
+    const profile: any = await program!.account.agentProfile.fetch(agent.profile);
+    const proofRecord = pda(
+      program!,
+      Buffer.from("zk_proof"),
+      agent.agentId.toBuffer(),
+      profile.proofCount.toArrayLike(Buffer, "le", 8)
+    );
+    const inputs = padTo([], 10).map(() => new BN(0));
+    inputs[0] = threshold;
+    await program!.methods
+      .submitZkProof({ reputationAbove: [threshold] }, padTo(proof, 500), proof.length, inputs, 1)
+      .accounts({
+        prover: agent.owner.publicKey,
+        proverProfile: agent.profile,
+        zkRegistry,
+        proofRecord,
+        systemProgram: SystemProgram.programId,
+        session: null,
+      })
+      .signers([agent.owner])
+      .rpc();
+    return proofRecord;
+  }
+
+  it("Verifies a ReputationAbove proof against a blinded commitment", async function () {
+    if (!program) {
+      console.log("Skipping - see circuits/README.md");
+      return;
+    }
+    const commitmentRegistry = await registerKey({ commitment: {} }, 3, "reputation_commitment");
+    const aboveRegistry = await registerKey({ above: {} }, 0, "reputation_above");
+    const agent = await registerAgent(program, provider, uniqueName("prover"));
+    await awardReputation(program, provider, protocol, agent, 1500);
+
+    const { blinding, commitment } = await commit(agent, commitmentRegistry);
+    const profile: any = await program.account.agentProfile.fetch(agent.profile);
+    assert.deepEqual(Array.from(profile.reputationCommitment), fieldBytes(commitment));
+
+    const threshold = new BN(1000);
+    const proof = await prove("reputation_above", {
+      threshold: threshold.toString(),
+      commitment,
+      reputation: "1500",
+      blinding,
+    });
+    const proofRecord = await submitAbove(agent, aboveRegistry, threshold, proof);
+    const record: any = await program.account.zkProofRecord.fetch(proofRecord);
+    assert.isTrue(record.verified);
+  });
+
+  it("Won't commit to a score other than the on-chain one", async function () {
+    if (!program) {
+      console.log("Skipping - see circuits/README.md");
+      return;
+    }
+    const commitmentRegistry = await registerKey({ commitment: {} }, 3, "reputation_commitment");
+    const agent = await registerAgent(program, provider, uniqueName("prover"));
+    await awardReputation(program, provider, protocol, agent, 500);
+
+    // A valid proof, but for a made-up score
+    const blinding = "12345";
+    const commitment = poseidon.F.toString(poseidon(["5000", blinding]));
+    const proof = await prove("reputation_commitment", { reputation: "5000", commitment, blinding });
+    await expectError(
+      program.methods
+        .commitReputation(fieldBytes(commitment), padTo(proof, 500), proof.length)
+        .accounts({
+          prover: agent.owner.publicKey,
+          proverProfile: agent.profile,
+          zkRegistry: commitmentRegistry,
+          session: null,
+        })
+        .signers([agent.owner])
+        .rpc(),
+      "InvalidProof"
+    );
+  });
+
+  it("Expires the commitment when reputation changes", async function () {
+    if (!program) {
+      console.log("Skipping - see circuits/README.md");
+      return;
+    }
+    const commitmentRegistry = await registerKey({ commitment: {} }, 3, "reputation_commitment");
+    const aboveRegistry = await registerKey({ above: {} }, 0, "reputation_above");
+    const agent = await registerAgent(program, provider, uniqueName("prover"));
+    await awardReputation(program, provider, protocol, agent, 1500);
+    const { blinding, commitment } = await commit(agent, commitmentRegistry);
+    const proof = await prove("reputation_above", { threshold: "1000", commitment, reputation: "1500", blinding });
+
+    await awardReputation(program, provider, protocol, agent, 100);
+    await expectError(submitAbove(agent, aboveRegistry, new BN(1000), proof), "ReputationNotCommitted");
+  });
+});
+
+describe("zk_statements", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = loadProgram(provider);
+  const noInputs = new Array(10).fill(new BN(0));
+  const emptyProof = new Array(500).fill(0);
+
+  async function submit(statement: any, zkRegistry: PublicKey | null) {
+    const agent = await registerAgent(program!, provider, uniqueName("prover"));
+    const profile: any = await program!.account.agentProfile.fetch(agent.profile);
+    const inputs = [...noInputs];
+    inputs[0] = new BN(100);
+    return program!.methods
+      .submitZkProof(statement, emptyProof, 0, inputs, 1)
+      .accounts({
+        prover: agent.owner.publicKey,
+        proverProfile: agent.profile,
+        zkRegistry,
+        proofRecord: pda(
+          program!,
+          Buffer.from("zk_proof"),
+          agent.agentId.toBuffer(),
+          profile.proofCount.toArrayLike(Buffer, "le", 8)
+        ),
+        systemProgram: SystemProgram.programId,
+        session: null,
+      })
+      .signers([agent.owner])
+      .rpc();
+  }
+
+  it("Rejects statements that have no circuit", async function () {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    await setupProtocol(program, provider);
+
+    await expectError(submit({ reputationBelow: [new BN(100)] }, null), "UnsupportedZKStatement");
+    await expectError(
+      submit({ reputationInRange: [new BN(100), new BN(200)] }, null),
+      "UnsupportedZKStatement"
+    );
+  });
+
+  it("Won't register a key for a circuit that doesn't exist", async function () {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const { config } = await setupProtocol(program, provider);
+
+    await expectError(
+      program.methods
+        .initializeZkRegistry({ below: {} }, new Array(32).fill(0), new Array(1000).fill(0), 576)
+        .accounts({
+          authority: provider.wallet.publicKey,
+          config,
+          zkRegistry: pda(program, Buffer.from("zk_registry"), Buffer.from([1])),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc(),
+      "UnsupportedZKStatement"
+    );
+  });
+
+  it("Won't check a statement against another circuit's key", async function () {
+    if (!program) {
+      console.log("Skipping - program not built");
+      return;
+    }
+    const { config } = await setupProtocol(program, provider);
+    const aboveRegistry = pda(program, Buffer.from("zk_registry"), Buffer.from([0]));
+
+    if (!(await program.account.zkVerificationKey.fetchNullable(aboveRegistry))) {
+      // Placeholder key of the right shape: header plus two IC points
+      await program.methods
+        .initializeZkRegistry({ above: {} }, new Array(32).fill(0), new Array(1000).fill(0), 576)
+        .accounts({
+          authority: provider.wallet.publicKey,
+          config,
+          zkRegistry: aboveRegistry,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    await expectError(submit({ reputationBelow: [new BN(100)] }, aboveRegistry), "UnsupportedZKStatement");
+  });
+});